@@ -69,6 +69,21 @@ const atomStyle = {
         { type: 'line', points: [ 0.3, 0, 0.45, 0.27, 0.15, 0.27 ] },
         { type: 'line', points: [ -0.3, 0, -0.45, 0.27, -0.15, 0.27 ] },
     ] },
+    cardinal: { shadowStyle: 'black', fillStyle: '#8ab', symbolStyle: '#bdf', symbol: [
+        { type: 'text', text: 'C' },
+    ] },
+    metal: { shadowStyle: 'black', fillStyle: '#976', symbolStyle: '#cb9', symbol: [
+        { type: 'text', text: 'M' },
+    ] },
+    'salt-quicksilver': { shadowStyle: 'black', fillStyle: '#bbb', symbolStyle: '#888', symbol: [
+        { type: 'text', text: 'SQ' },
+    ] },
+    'vitae-mors': { shadowStyle: 'black', fillStyle: '#a88', symbolStyle: '#dbb', symbol: [
+        { type: 'text', text: 'VM' },
+    ] },
+    any: { shadowStyle: 'black', fillStyle: '#666', symbolStyle: '#aaa', symbol: [
+        { type: 'text', text: '?' },
+    ] },
     repeat: { shadowStyle: 'black', fillStyle: '#555', symbolStyle: '#333', symbol: [
         { type: 'arc', x: 0, y: 0, radius: 0.05, from: 0, to: 1 },
         { type: 'arc', x: -0.4, y: 0, radius: 0.05, from: 0, to: 1 },
@@ -104,6 +119,12 @@ function drawAtom(ctx, atom, x, y) {
                 ctx.lineTo(s.points[i], s.points[i + 1]);
             ctx.closePath();
             ctx.stroke();
+        } else if (s.type == 'text') {
+            ctx.fillStyle = atomStyle[atom].symbolStyle;
+            ctx.font = '0.7px sans-serif';
+            ctx.textAlign = 'center';
+            ctx.textBaseline = 'middle';
+            ctx.fillText(s.text, 0, 0);
         }
     }
     ctx.restore();
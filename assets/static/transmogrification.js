@@ -1,7 +1,7 @@
 const canvas = document.getElementById('current');
 const nextCanvas = document.getElementById('next');
-const drawerWidth = 264;
-const width = 1001;
+const drawerWidth = 346;
+const width = 1083;
 const height = 652;
 nextCanvas.width = canvas.width = width * window.devicePixelRatio;
 nextCanvas.height = canvas.height = height * window.devicePixelRatio;
@@ -10,55 +10,12 @@ canvas.style.height = `${canvas.height / window.devicePixelRatio}px`;
 
 let mouseX = 0;
 let mouseY = 0;
-let state = {
+let state = Object.assign({
     'selectedAtom': 'salt',
     'selectedBond': 'n',
-};
+}, initialState);
 let nextState = state;
 
-const atomsByEncoding = [
-    'salt',
-    'air',
-    'earth',
-    'fire',
-    'water',
-    'quicksilver',
-    'gold',
-    'silver',
-    'copper',
-    'iron',
-    'tin',
-    'lead',
-    'vitae',
-    'mors',
-    'quintessence',
-];
-const atomEncoding = {
-    salt: 0n,
-    air: 1n,
-    earth: 2n,
-    fire: 3n,
-    water: 4n,
-    quicksilver: 5n,
-    gold: 6n,
-    silver: 7n,
-    copper: 8n,
-    iron: 9n,
-    tin: 10n,
-    lead: 11n,
-    vitae: 12n,
-    mors: 13n,
-    quintessence: 14n,
-};
-function bondEncoding(bond) {
-    if (bond === 'n')
-        return 1n;
-    else if (bond === 'ryk')
-        return 2n;
-    else
-        return 0n;
-}
-
 function visit(atom, bond) {
     for (let i = -4; i <= 4; ++i) {
         for (let j = -4; j <= 4; ++j) {
@@ -81,6 +38,7 @@ function visitDrawer(atom, bond) {
     const x0 = 50;
     const x1 = x0 + spacing;
     const x2 = x1 + spacing;
+    const x3 = x2 + spacing;
     if (bond) {
         bond(x0, 55 + spacing * 5, 'n');
         bond(x1, 55 + spacing * 5, 'ryk');
@@ -108,6 +66,11 @@ function visitDrawer(atom, bond) {
         atom(x1, 55 + spacing * 4, 'mors');
         atom(x2, 55 + spacing * 4, 'quintessence');
         atom(x2, 55 + spacing * 5, 'repeat');
+        atom(x3, 55, 'cardinal');
+        atom(x3, 55 + spacing, 'metal');
+        atom(x3, 55 + spacing * 2, 'salt-quicksilver');
+        atom(x3, 55 + spacing * 3, 'vitae-mors');
+        atom(x3, 55 + spacing * 4, 'any');
     }
 }
 
@@ -338,72 +301,6 @@ function updateNextState() {
             nextState[bondKey] = nextState['selectedBond'];
     }
 }
-function sortedAtomPositions(state) {
-    return Object.keys(state).map(function (a) {
-        return a.split(',').map(function (n) { return parseInt(n, 10); });
-    }).filter(function (a) {
-        return a.length === 2;
-    }).sort(function (a, b) {
-        if (a[0] < b[0])
-            return -1;
-        if (a[0] > b[0])
-            return 1;
-        if (a[1] < b[1])
-            return -1;
-        if (a[1] > b[1])
-            return 1;
-        return 0;
-    });
-}
-function stateToNumber(state) {
-    const atomPositions = sortedAtomPositions(state);
-    let cursor;
-    let multiplier = 1n;
-    let number = 0n;
-    for (const position of atomPositions) {
-        if (cursor && cursor[0] !== position[0]) {
-            while (cursor[1] > position[1]) {
-                multiplier *= 3n;
-                cursor[1]--;
-            }
-            while (cursor[0] < position[0]) {
-                number += multiplier * 2n;
-                multiplier *= 3n;
-                cursor[0]++;
-            }
-        }
-        if (cursor && cursor[1] !== position[1]) {
-            cursor[1]++;
-            while (cursor[1] !== position[1]) {
-                multiplier *= 3n;
-                cursor[1]++;
-            }
-        }
-        if (cursor) {
-            number += multiplier;
-            multiplier *= 3n;
-        }
-        const atom = state[`${position[0]},${position[1]}`];
-        number += atomEncoding[atom] * multiplier;
-        multiplier *= 15n;
-        cursor = JSON.parse(JSON.stringify(position));
-    }
-    for (const position of atomPositions) {
-        if (state[`${position[0]+1},${position[1]}`]) {
-            number += bondEncoding(state[`${position[0]},${position[1]}:${position[0]+1},${position[1]}`]) * multiplier;
-            multiplier *= 3n;
-        }
-        if (state[`${position[0]-1},${position[1]+1}`]) {
-            number += bondEncoding(state[`${position[0]},${position[1]}:${position[0]-1},${position[1]+1}`]) * multiplier;
-            multiplier *= 3n;
-        }
-        if (state[`${position[0]},${position[1]+1}`]) {
-            number += bondEncoding(state[`${position[0]},${position[1]}:${position[0]},${position[1]+1}`]) * multiplier;
-            multiplier *= 3n;
-        }
-    }
-    return number;
-}
 function visitBondForValidation(state, result, stack, visited, p, u, v) {
     const bondNeighbor = [p[0] + u, p[1] + v];
     const bondKey = keyForBond(canonicalizeBond([p, bondNeighbor]));
@@ -441,203 +338,63 @@ function validateState(state) {
     }
     return result;
 }
-function triangular(index) {
-    let n = 1;
-    while (true) {
-        if (index < n)
-            return [ n, index ];
-        index -= n;
-        n++;
+function updateSubmission(notation) {
+    const form = document.getElementById('submission');
+    if (form === null)
+        return;
+    if (notation === null) {
+        form.style.display = 'none';
+    } else {
+        document.getElementById('submission-notation').value = notation;
+        form.style.display = '';
     }
 }
-function tetrahedral(index) {
-    let n = 2;
-    while (true) {
-        if (index < n * (n - 1) / 2) {
-            const [ a, b ] = triangular(index);
-            return [ n, a, b ];
-        }
-        index -= n * (n - 1) / 2;
-        n++;
-    }
+function stateToNotation(state) {
+    return Object.keys(state)
+        .filter(function (key) { return key !== 'selectedAtom' && key !== 'selectedBond'; })
+        .sort()
+        .map(function (key) { return `${key}=${state[key]}`; })
+        .join(';');
 }
-function stateForEnumerationIndex(index) {
-    if (index < 0)
-        throw 'index out of range';
-    if (index < 15)
-        return { '0,0': atomsByEncoding[index] };
-    index -= 15;
-    if (index < 120) {
-        const [ a, b ] = triangular(index);
-        return { '0,0': atomsByEncoding[a - 1], '1,0': atomsByEncoding[b], '0,0:1,0': 'n' };
-    }
-    index -= 120;
-    if (index < 1800) {
-        const b = index % 15;
-        const [ a, c ] = triangular(Math.floor(index / 15));
-        return {
-            '0,0': atomsByEncoding[a - 1],
-            '1,0': atomsByEncoding[b],
-            '2,0': atomsByEncoding[c],
-            '0,0:1,0': 'n',
-            '1,0:2,0': 'n',
-        };
-    }
-    index -= 1800;
-    if (index < 3375) {
-        const a = index % 15;
-        index = Math.floor(index / 15);
-        const b = index % 15;
-        index = Math.floor(index / 15);
-        const c = index % 15;
-        return {
-            '0,0': atomsByEncoding[a],
-            '1,-1': atomsByEncoding[b],
-            '1,0': atomsByEncoding[c],
-            '1,-1:0,0': 'n',
-            '1,-1:1,0': 'n',
-        };
-    }
-    index -= 3375;
-    if (index < 3375) {
-        const a = index % 15;
-        index = Math.floor(index / 15);
-        const b = index % 15;
-        index = Math.floor(index / 15);
-        const c = index % 15;
-        return {
-            '0,0': atomsByEncoding[a],
-            '1,-1': atomsByEncoding[b],
-            '2,-1': atomsByEncoding[c],
-            '1,-1:0,0': 'n',
-            '1,-1:2,-1': 'n',
-        };
-    }
-    index -= 3375;
-    if (index < 225) {
-        const a = index % 15;
-        index = Math.floor(index / 15);
-        const b = index % 15;
-        return {
-            '0,0': atomsByEncoding[a],
-            '1,-1': atomsByEncoding[b],
-            '1,0': atomsByEncoding[a],
-            '1,-1:0,0': 'n',
-            '1,-1:1,0': 'n',
-            '0,0:1,0': 'n',
-        };
-    }
-    index -= 225;
-    if (index < 910) {
-        const flip = index % 2;
-        index = Math.floor(index / 2);
-        const [ a, b, c ] = tetrahedral(index);
-        return {
-            '0,0': atomsByEncoding[a],
-            '1,-1': flip ? atomsByEncoding[b] : atomsByEncoding[c],
-            '1,0': flip ? atomsByEncoding[c] : atomsByEncoding[b],
-            '1,-1:0,0': 'n',
-            '1,-1:1,0': 'n',
-            '0,0:1,0': 'n',
-        };
-    }
-    index -= 910;
-    if (index < 15) {
-        return {
-            '0,0': 'fire',
-            '1,0': 'fire',
-            '2,0': atomsByEncoding[index],
-            '0,0:1,0': 'ryk',
-            '1,0:2,0': 'n',
-        };
-    }
-    index -= 15;
-    if (index < 15) {
-        return {
-            '0,0': 'fire',
-            '1,-1': 'fire',
-            '1,0': atomsByEncoding[index],
-            '1,-1:0,0': 'ryk',
-            '1,-1:1,0': 'n',
-        };
-    }
-    index -= 15;
-    if (index < 15) {
-        return {
-            '0,0': atomsByEncoding[index],
-            '1,-1': 'fire',
-            '1,0': 'fire',
-            '1,-1:0,0': 'n',
-            '1,-1:1,0': 'ryk',
-        };
-    }
-    index -= 15;
-    if (index < 15) {
-        return {
-            '0,0': 'fire',
-            '1,-1': 'fire',
-            '2,-1': atomsByEncoding[index],
-            '1,-1:0,0': 'ryk',
-            '1,-1:2,-1': 'n',
-        };
-    }
-    index -= 15;
-    if (index < 15) {
-        return {
-            '0,0': atomsByEncoding[index],
-            '1,-1': 'fire',
-            '2,-1': 'fire',
-            '1,-1:0,0': 'n',
-            '1,-1:2,-1': 'ryk',
-        };
-    }
-    index -= 15;
-    if (index < 15) {
-        return {
-            '0,0': 'fire',
-            '1,-1': atomsByEncoding[index],
-            '1,0': 'fire',
-            '1,-1:0,0': 'n',
-            '1,-1:1,0': 'n',
-            '0,0:1,0': 'ryk',
-        };
-    }
-    index -= 15;
-    switch (index) {
-    case 0:
-        return { '0,0': 'fire', '1,0': 'fire', '0,0:1,0': 'ryk' };
-    case 1:
-        return { '0,0': 'fire', '1,0': 'fire', '2,0': 'fire', '0,0:1,0': 'ryk', '1,0:2,0': 'ryk' };
-    case 2:
-        return { '0,0': 'fire', '1,-1': 'fire', '1,0': 'fire', '1,-1:0,0': 'ryk', '1,-1:1,0': 'ryk' };
-    case 3:
-        return { '0,0': 'fire', '1,-1': 'fire', '2,-1': 'fire', '1,-1:0,0': 'ryk', '1,-1:2,-1': 'ryk' };
-    case 4:
-        return { '0,0': 'fire', '1,-1': 'fire', '1,0': 'fire', '1,-1:0,0': 'ryk', '1,-1:1,0': 'ryk', '0,0:1,0': 'n' };
-    case 5:
-        return { '0,0': 'fire', '1,-1': 'fire', '1,0': 'fire', '1,-1:0,0': 'ryk', '1,-1:1,0': 'ryk', '0,0:1,0': 'ryk' };
-    default:
-        throw 'number out of range';
-    }
+function updateUrl() {
+    const params = new URLSearchParams();
+    const notation = stateToNotation(state);
+    if (notation !== '')
+        params.set('molecule', notation);
+    const source = document.getElementById('source').value;
+    if (source !== '')
+        params.set('source', source);
+    const query = params.toString();
+    history.replaceState(null, '', `${basePath}/${query === '' ? '' : `?${query}`}`);
 }
 async function updateDownload() {
+    updateSubmission(null);
+    updateUrl();
     const validationResult = validateState(state);
     if (validationResult.empty) {
         document.getElementById('default').style.display = '';
         document.getElementById('error').textContent = '';
         document.getElementById('result').style.display = 'none';
+        document.getElementById('share').style.display = 'none';
         return;
     } else if (validationResult.error) {
         document.getElementById('default').style.display = 'none';
         document.getElementById('error').textContent = validationResult.error;
         document.getElementById('result').style.display = 'none';
+        document.getElementById('share').style.display = 'none';
         return;
     } else {
         document.getElementById('default').style.display = 'none';
         document.getElementById('error').textContent = '';
         document.getElementById('result').style.display = '';
+        document.getElementById('share').style.display = '';
     }
-    let response = await fetch(new Request('/api/v1/molecule-from-state', {
+    const params = new URLSearchParams();
+    const source = document.getElementById('source').value;
+    if (source !== '')
+        params.set('source', source);
+    const query = params.toString();
+    let response = await fetch(new Request(`${basePath}/api/v1/molecule-from-state${query === '' ? '' : `?${query}`}`, {
         method: 'POST',
         headers: {
             'Content-Type': 'application/json',
@@ -646,8 +403,17 @@ async function updateDownload() {
     }));
     if (response.ok) {
         let data = await response.json();
-        if (data.appearances.length === 0) {
+        if (data.rustCode === null) {
+            if (data.matches.length === 0) {
+                document.getElementById('result').textContent = 'no matching molecules';
+            } else {
+                document.getElementById('result').innerHTML = data.matches
+                    .map(function (appearances) { return appearances.map(JSON.stringify).join('<br />'); })
+                    .join('<br /><br />');
+            }
+        } else if (data.appearances.length === 0) {
             document.getElementById('result').textContent = 'unknown molecule';
+            updateSubmission(data.notation);
             document.getElementById('result').addEventListener('click', async function (e) {
                 await navigator.clipboard.writeText(data.rustCode);
             })
@@ -656,6 +422,24 @@ async function updateDownload() {
                 .map(JSON.stringify)
                 .join('<br />');
         }
+        if (data.suggestions.length > 0) {
+            document.getElementById('result').innerHTML += '<br />did you mean:<br />' + data.suggestions
+                .map(function (suggestion) { return suggestion.appearances.map(JSON.stringify).join('<br />') + ` (${suggestion.distance} ${suggestion.distance === 1 ? 'edit' : 'edits'} away)`; })
+                .join('<br />');
+        }
+        if (data.sameShape.length > 0) {
+            document.getElementById('result').innerHTML += '<br />same shape as:<br />' + data.sameShape
+                .map(function (appearances) { return appearances.map(JSON.stringify).join('<br />'); })
+                .join('<br />');
+        }
+        if (data.containing.length > 0) {
+            document.getElementById('result').innerHTML += '<br />part of:<br />' + data.containing
+                .map(function (appearances) { return appearances.map(JSON.stringify).join('<br />'); })
+                .join('<br />');
+        }
+    } else if (response.status === 413 || response.status === 429) {
+        document.getElementById('error').textContent = (await response.json()).error;
+        document.getElementById('result').style.display = 'none';
     } else {
         throw new Error('molecule lookup failed');
     }
@@ -696,6 +480,14 @@ window.addEventListener('mousemove', async function (e) {
     if (mouseDown)
         await updateDownload();
 });
+document.getElementById('source').addEventListener('change', async function (e) {
+    await updateDownload();
+});
+document.getElementById('copy-link').addEventListener('click', async function (e) {
+    await navigator.clipboard.writeText(window.location.href);
+    e.target.textContent = 'Link copied';
+    setTimeout(function () { e.target.textContent = 'Copy link to this molecule'; }, 2000);
+});
 canvas.addEventListener('contextmenu', function (e) {
     e.preventDefault();
 });
@@ -714,3 +506,5 @@ window.addEventListener('mouseup', function (e) {
     updateNextState();
     redraw();
 });
+if (Object.keys(initialState).length > 0)
+    updateDownload();
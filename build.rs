@@ -0,0 +1,41 @@
+//! Hashes everything compiled into the server, so cacheable responses can be versioned without reading the binary at runtime.
+
+use std::{
+    fs,
+    io,
+    path::Path,
+};
+
+/// Hashes the paths and contents of all files in the given directory in a deterministic order, using 64-bit FNV-1a like `util::fnv1a`.
+fn hash_dir(hash: &mut u64, dir: &Path) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.map(|entry| entry.map(|entry| entry.path())).collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            hash_dir(hash, &path)?;
+        } else {
+            hash_bytes(hash, path.to_string_lossy().replace('\\', "/").as_bytes());
+            hash_bytes(hash, &fs::read(&path)?);
+        }
+    }
+    Ok(())
+}
+
+fn hash_bytes(hash: &mut u64, bytes: &[u8]) {
+    *hash = bytes.iter().fold(*hash, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+}
+
+fn main() -> io::Result<()> {
+    let mut hash = 0xcbf2_9ce4_8422_2325;
+    for dir in ["src", "assets"] {
+        println!("cargo:rerun-if-changed={dir}");
+        hash_dir(&mut hash, Path::new(dir))?;
+    }
+    // dependencies like omsim-rs affect responses too
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    if let Ok(lockfile) = fs::read("Cargo.lock") {
+        hash_bytes(&mut hash, &lockfile);
+    }
+    println!("cargo:rustc-env=MOLECULE_DB_BUILD_HASH={hash:016x}");
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use {
+    chrono::prelude::*,
+    rocket::{
+        Request,
+        Response,
+        http::Status,
+        outcome::Outcome,
+        request::{
+            self,
+            FromRequest,
+        },
+        response::{
+            self,
+            Responder,
+        },
+    },
+    crate::{
+        asset_version,
+        changelog,
+        util::fnv1a,
+    },
+};
+
+/// Routes serving the static assets compiled into the binary, see [`crate::static_url`].
+const ASSET_ROUTES: [(&str, &str); 3] = [
+    ("common_css", "common.css"),
+    ("common_js", "common.js"),
+    ("transmogrification_js", "transmogrification.js"),
+];
+
+fn http_date(date: DateTime<Utc>) -> String {
+    date.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// The version of everything cacheable responses are derived from, computed once at startup.
+pub(crate) struct ContentVersion {
+    /// A hash of the package version, of the source code and assets the server was built from (see `build.rs`), which include the catalogue, the templates, and the static assets, and of the settings which affect responses.
+    hash: u64,
+    /// The date the catalogue was last changed according to the changelog.
+    catalogue_date: Option<DateTime<Utc>>,
+}
+
+impl ContentVersion {
+    pub(crate) fn compute(settings: &str) -> Self {
+        Self {
+            hash: fnv1a(format!("{}\n{}\n{settings}", env!("CARGO_PKG_VERSION"), env!("MOLECULE_DB_BUILD_HASH")).as_bytes()),
+            catalogue_date: changelog::changelog().into_iter().map(|(date, _, _)| date).max().map(|date| date.and_time(NaiveTime::MIN).and_utc()),
+        }
+    }
+}
+
+/// A request guard for routes whose responses only depend on the request URI and the [`ContentVersion`].
+///
+/// Caching is opt-in: only routes which take this guard and respond using [`Cache::respond`] send validators and answer conditional requests.
+pub(crate) struct Cache {
+    etag: String,
+    fingerprinted: bool,
+    /// Whether the client's cached copy has this ETag, or `None` if the client didn't send any.
+    etag_matches: Option<bool>,
+    if_modified_since: Option<DateTime<FixedOffset>>,
+    catalogue_date: Option<DateTime<Utc>>,
+    last_modified: Option<DateTime<Utc>>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Cache {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, ()> {
+        let version = req.rocket().state::<ContentVersion>().expect("content version is managed at startup");
+        let name = req.route().and_then(|route| route.name.as_deref()).unwrap_or_default();
+        let etag = format!("\"{:016x}\"", fnv1a(format!("{:016x}\n{name}\n{}", version.hash, req.uri()).as_bytes()));
+        let fingerprinted = ASSET_ROUTES.iter().find(|(route, _)| *route == name).is_some_and(|(_, file)| {
+            req.query_value::<&str>("v").and_then(Result::ok) == Some(&*format!("{:016x}", asset_version(file)))
+        });
+        let etag_matches = req.headers().get_one("If-None-Match").map(|if_none_match| {
+            if_none_match.split(',').map(|tag| tag.trim().trim_start_matches("W/")).any(|tag| tag == etag)
+        });
+        let if_modified_since = req.headers().get_one("If-Modified-Since").and_then(|if_modified_since| DateTime::parse_from_rfc2822(if_modified_since).ok());
+        Outcome::Success(Self { etag, fingerprinted, etag_matches, if_modified_since, catalogue_date: version.catalogue_date, last_modified: None })
+    }
+}
+
+impl Cache {
+    /// Sends the date the catalogue was last changed as `Last-Modified`, and answers `If-Modified-Since` with it.
+    /// Only for responses which change with the catalogue but not with the templates, like exports and feeds. Other responses are only validated by their ETag.
+    pub(crate) fn with_catalogue_date(mut self) -> Self {
+        self.last_modified = self.catalogue_date;
+        self
+    }
+
+    /// If-Modified-Since is only used by clients which don't have an ETag.
+    fn fresh(&self) -> bool {
+        self.etag_matches.unwrap_or_else(|| self.if_modified_since.zip(self.last_modified).is_some_and(|(if_modified_since, last_modified)| last_modified <= if_modified_since))
+    }
+
+    /// Renders the response unless the client's cached copy is still fresh, in which case it's answered with 304 Not Modified.
+    pub(crate) fn respond<R>(self, render: impl FnOnce() -> R) -> Cached<R> {
+        let response = (!self.fresh()).then(render);
+        Cached { cache: self, response }
+    }
+}
+
+/// A response with `ETag`, `Cache-Control`, and possibly `Last-Modified` headers, see [`Cache::respond`].
+pub(crate) struct Cached<R> {
+    cache: Cache,
+    response: Option<R>,
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for Cached<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'o> {
+        let mut response = if let Some(response) = self.response {
+            let response = response.respond_to(request)?;
+            if response.status() != Status::Ok {
+                return Ok(response)
+            }
+            response
+        } else {
+            Response::build().status(Status::NotModified).finalize()
+        };
+        response.set_raw_header("Cache-Control", if self.cache.fingerprinted { "public, max-age=31536000, immutable" } else { "public, no-cache" });
+        response.set_raw_header("ETag", self.cache.etag);
+        if let Some(last_modified) = self.cache.last_modified {
+            response.set_raw_header("Last-Modified", http_date(last_modified));
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        rocket::{
+            http::Header,
+            local::blocking::Client,
+        },
+        crate::Settings,
+        super::*,
+    };
+
+    #[test]
+    fn conditional_requests() {
+        let client = Client::tracked(crate::rocket(rocket::Config::figment(), Settings::default()).expect("failed to configure server")).expect("failed to start server");
+        let response = client.get("/stats").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let etag = response.headers().get_one("ETag").expect("no ETag on a cacheable route").to_owned();
+        let response = client.get("/stats").header(Header::new("If-None-Match", etag.clone())).dispatch();
+        assert_eq!(response.status(), Status::NotModified);
+        assert_eq!(response.headers().get_one("ETag"), Some(&*etag));
+        let response = client.get("/stats?unrelated").header(Header::new("If-None-Match", etag)).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let response = client.get("/healthz").dispatch();
+        assert_eq!(response.headers().get_one("ETag"), None);
+    }
+
+    #[test]
+    fn last_modified_only_for_catalogue_content() {
+        let client = Client::tracked(crate::rocket(rocket::Config::figment(), Settings::default()).expect("failed to configure server")).expect("failed to start server");
+        let response = client.get("/stats").dispatch();
+        assert_eq!(response.headers().get_one("Last-Modified"), None);
+        let response = client.get("/stats").header(Header::new("If-Modified-Since", "Fri, 31 Dec 9999 23:59:59 GMT")).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let response = client.get("/export/catalogue.json").dispatch();
+        let last_modified = response.headers().get_one("Last-Modified").expect("no Last-Modified on an export").to_owned();
+        let response = client.get("/export/catalogue.json").header(Header::new("If-Modified-Since", last_modified)).dispatch();
+        assert_eq!(response.status(), Status::NotModified);
+    }
+}
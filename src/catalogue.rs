@@ -0,0 +1,220 @@
+use {
+    std::fmt::Write as _,
+    chrono::prelude::*,
+    itertools::Itertools as _,
+    omsim_rs::data::*,
+    crate::{
+        InOut,
+        changelog,
+        molecules,
+        puzzles::{
+            self,
+            Source,
+        },
+        unparse::Unparse,
+    },
+};
+
+const ENTRY_START: &str = "        (Molecule {";
+const ENTRY_END: &str = "        ]),";
+const CATALOGUE_END: &str = "    ]";
+const CHANGELOG_START: &str = "    vec![";
+const VERSION_PREFIX: &str = "pub(crate) const CATALOGUE_VERSION: u32 = ";
+const HASH_PREFIX: &str = "pub(crate) const CATALOGUE_HASH: u64 = ";
+const SOURCES_PREFIX: &str = "pub(crate) const SOURCES: [(&str, Source); ";
+const SOURCES_END: &str = "];";
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("this appearance is already in the catalogue")]
+    Duplicate,
+    #[error("the catalogue source code doesn't match the catalogue this was built with, rebuild and try again")]
+    OutOfSync,
+    #[error("couldn't find the list of changes, the catalogue version, or the catalogue hash in the changelog source code")]
+    Changelog,
+    #[error("this puzzle isn't in puzzles::SOURCES yet, specify where it's from using --source")]
+    MissingSource,
+    #[error("this puzzle is already listed in puzzles::SOURCES with a different source")]
+    SourceConflict,
+    #[error("weekly puzzles are marked with a `*` suffix on their name instead of being listed in puzzles::SOURCES")]
+    WeeklySource,
+}
+
+fn source_code(source: Source) -> &'static str {
+    match source {
+        Source::Campaign => "Source::Campaign",
+        Source::Journal => "Source::Journal",
+        Source::Tournament => "Source::Tournament",
+        Source::Weekly => "Source::Weekly",
+    }
+}
+
+fn inout_code(inout: &InOut) -> &'static str {
+    match inout {
+        InOut::Reagent => "InOut::Reagent",
+        InOut::Product => "InOut::Product",
+        InOut::Both => "InOut::Both",
+    }
+}
+
+fn appearance_line(puzzle: &str, inout: &InOut, name: &str) -> String {
+    format!("            ({puzzle:?}, {}, {name:?}),", inout_code(inout))
+}
+
+/// Formats a molecule and its appearances the way entries are written in `src/molecules.rs`.
+pub(crate) fn entry(molecule: &Molecule, appearances: &[(&str, InOut, &str)]) -> String {
+    let mut entry = format!("        ({:?}, vec![\n", Unparse(molecule));
+    for (puzzle, inout, name) in appearances {
+        writeln!(entry, "{}", appearance_line(puzzle, inout, name)).expect("writing to a String can't fail");
+    }
+    entry.push_str("        ]),\n");
+    entry
+}
+
+/// Adds an appearance of the given normalized molecule to the source code of `src/molecules.rs`.
+///
+/// The catalogue lists entries in the order they were added, and the appearances of each entry in the order they were added.
+/// So if the molecule is already in the catalogue, the appearance is appended to its entry.
+/// Otherwise, a new entry is appended to the catalogue.
+pub(crate) fn add_appearance(source: &str, molecule: &Molecule, puzzle: &str, inout: InOut, name: &str) -> Result<String, Error> {
+    let mut lines = source.lines().map(str::to_owned).collect_vec();
+    let starts = lines.iter().positions(|line| line.starts_with(ENTRY_START)).collect_vec();
+    let known = molecules::molecules();
+    if starts.len() != known.len() {
+        return Err(Error::OutOfSync)
+    }
+    if let Some(idx) = known.iter().position(|(iter_molecule, _)| iter_molecule == molecule) {
+        if known[idx].1.iter().any(|&(iter_puzzle, _, iter_name)| iter_puzzle == puzzle && iter_name == name) {
+            return Err(Error::Duplicate)
+        }
+        let end = lines[starts[idx]..].iter().position(|line| line == ENTRY_END).ok_or(Error::OutOfSync)?;
+        lines.insert(starts[idx] + end, appearance_line(puzzle, &inout, name));
+    } else {
+        let insert_at = lines.iter().rposition(|line| line == CATALOGUE_END).ok_or(Error::OutOfSync)?;
+        let new_entry = entry(molecule, &[(puzzle, inout, name)]);
+        lines.splice(insert_at..insert_at, new_entry.lines().map(str::to_owned));
+    }
+    let mut source = lines.join("\n");
+    source.push('\n');
+    Ok(source)
+}
+
+/// Records that the given appearance was added to the catalogue on the given date in the source code of `src/changelog.rs`.
+pub(crate) fn add_to_changelog(source: &str, puzzle: &str, name: &str, date: NaiveDate) -> Result<String, Error> {
+    if source.contains(&format!(", {puzzle:?}, {name:?}),")) {
+        return Err(Error::Duplicate)
+    }
+    let mut lines = source.lines().map(str::to_owned).collect_vec();
+    let start = lines.iter().position(|line| line == CHANGELOG_START).ok_or(Error::Changelog)?;
+    lines.insert(start + 1, format!("        (date({}, {}, {}), {puzzle:?}, {name:?}),", date.year(), date.month(), date.day()));
+    let mut source = lines.join("\n");
+    source.push('\n');
+    Ok(source)
+}
+
+/// Lists a puzzle in `puzzles::SOURCES` in the source code of `src/puzzles.rs`, keeping it sorted by name.
+///
+/// Returns `None` if no change is needed, since the puzzle is a weekly or already listed. Otherwise, its source must be given.
+pub(crate) fn add_source(source: &str, puzzle: &str, puzzle_source: Option<Source>) -> Result<Option<String>, Error> {
+    if let Some(known_source) = Source::of(puzzle) {
+        return if puzzle_source.map_or(true, |puzzle_source| puzzle_source == known_source) { Ok(None) } else { Err(Error::SourceConflict) }
+    }
+    let puzzle_source = puzzle_source.ok_or(Error::MissingSource)?;
+    if puzzle_source == Source::Weekly {
+        return Err(Error::WeeklySource)
+    }
+    let mut lines = source.lines().map(str::to_owned).collect_vec();
+    let start = lines.iter().position(|line| line.starts_with(SOURCES_PREFIX)).ok_or(Error::OutOfSync)?;
+    let len = lines[start..].iter().position(|line| line == SOURCES_END).ok_or(Error::OutOfSync)? - 1;
+    if len != puzzles::SOURCES.len() {
+        return Err(Error::OutOfSync)
+    }
+    let idx = puzzles::SOURCES.partition_point(|&(name, _)| name < puzzle);
+    lines.insert(start + 1 + idx, format!("    ({puzzle:?}, {}),", source_code(puzzle_source)));
+    lines[start] = format!("{SOURCES_PREFIX}{}] = [", len + 1);
+    let mut source = lines.join("\n");
+    source.push('\n');
+    Ok(Some(source))
+}
+
+/// Increments the catalogue version in the source code of `src/changelog.rs` and updates the catalogue hash to that of the given new source code of `src/molecules.rs`.
+pub(crate) fn bump_version(source: &str, catalogue_source: &str) -> Result<String, Error> {
+    let mut lines = source.lines().map(str::to_owned).collect_vec();
+    let line = lines.iter_mut().find(|line| line.starts_with(VERSION_PREFIX)).ok_or(Error::Changelog)?;
+    let version = line[VERSION_PREFIX.len()..].trim_end_matches(';').parse::<u32>().map_err(|_| Error::Changelog)?;
+    *line = format!("{VERSION_PREFIX}{};", version + 1);
+    let line = lines.iter_mut().find(|line| line.starts_with(HASH_PREFIX)).ok_or(Error::Changelog)?;
+    *line = format!("{HASH_PREFIX}0x{:016x};", changelog::catalogue_hash(catalogue_source));
+    let mut source = lines.join("\n");
+    source.push('\n');
+    Ok(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::changelog::CATALOGUE_VERSION,
+        super::*,
+    };
+
+    const CATALOGUE: &str = include_str!("molecules.rs");
+    const CHANGELOG: &str = include_str!("changelog.rs");
+    const PUZZLES: &str = include_str!("puzzles.rs");
+
+    fn known(name: &str) -> Molecule {
+        molecules::molecules().into_iter()
+            .find(|(_, appearances)| appearances.iter().any(|&(_, _, iter_name)| iter_name == name))
+            .expect("molecule not in catalogue")
+            .0
+    }
+
+    #[test]
+    fn new_entry() {
+        let molecule = Molecule {
+            atoms: [(HexIndex { q: 0, r: 0 }, Atom::Quintessence), (HexIndex { q: 1, r: 0 }, Atom::Quintessence)].into_iter().collect(),
+            bonds: [Bond { start: HexIndex { q: 0, r: 0 }, end: HexIndex { q: 1, r: 0 }, ty: BondType::Triplex { red: true, black: false, yellow: false } }].into_iter().collect(),
+        };
+        assert!(molecules::molecules().into_iter().all(|(iter_molecule, _)| iter_molecule != molecule));
+        let new_source = add_appearance(CATALOGUE, &molecule, "Test Puzzle", InOut::Product, "Test Molecule").expect("failed to add entry");
+        let end = CATALOGUE.rfind("    ]\n").expect("catalogue end not found");
+        let new_entry = entry(&molecule, &[("Test Puzzle", InOut::Product, "Test Molecule")]);
+        assert_eq!(new_source, format!("{}{new_entry}{}", &CATALOGUE[..end], &CATALOGUE[end..]));
+    }
+
+    #[test]
+    fn existing_entry() {
+        let molecule = known("Pizza Slice");
+        let new_source = add_appearance(CATALOGUE, &molecule, "Test Puzzle", InOut::Reagent, "Pizza Slice").expect("failed to add appearance");
+        let old_appearance = format!("{}\n", appearance_line("Pizza", &InOut::Product, "Pizza Slice"));
+        let new_appearance = format!("{}\n", appearance_line("Test Puzzle", &InOut::Reagent, "Pizza Slice"));
+        assert_eq!(new_source, CATALOGUE.replacen(&old_appearance, &format!("{old_appearance}{new_appearance}"), 1));
+        assert!(matches!(add_appearance(CATALOGUE, &molecule, "Pizza", InOut::Product, "Pizza Slice"), Err(Error::Duplicate)));
+    }
+
+    #[test]
+    fn changelog_entry() {
+        let date = NaiveDate::from_ymd_opt(2025, 2, 3).expect("invalid date");
+        let new_source = add_to_changelog(CHANGELOG, "Test Puzzle", "Test Molecule", date).expect("failed to add to changelog");
+        assert_eq!(new_source, CHANGELOG.replacen(
+            &format!("{CHANGELOG_START}\n"),
+            &format!("{CHANGELOG_START}\n        (date(2025, 2, 3), \"Test Puzzle\", \"Test Molecule\"),\n"),
+            1,
+        ));
+        assert!(matches!(add_to_changelog(CHANGELOG, "Thermic Capacitor*", "Argentic Glass", date), Err(Error::Duplicate)));
+        let bumped = bump_version(&new_source, CATALOGUE).expect("failed to bump version");
+        assert!(bumped.contains(&format!("{VERSION_PREFIX}{};", CATALOGUE_VERSION + 1)));
+        assert!(bumped.contains(&format!("{HASH_PREFIX}0x{:016x};", changelog::catalogue_hash(CATALOGUE))));
+    }
+    #[test]
+    fn source_entry() {
+        let new_source = add_source(PUZZLES, "Test Puzzle", Some(Source::Tournament)).expect("failed to add source").expect("source not added");
+        let len = puzzles::SOURCES.len();
+        assert!(new_source.contains(&format!("{SOURCES_PREFIX}{}] = [", len + 1)));
+        assert!(new_source.contains("    (\"Synthetic Malachite\", Source::Journal),\n    (\"Test Puzzle\", Source::Tournament),\n    (\"Thermite Filament\", Source::Tournament),\n"));
+        assert!(matches!(add_source(PUZZLES, "Test Puzzle", None), Err(Error::MissingSource)));
+        assert!(matches!(add_source(PUZZLES, "Test Puzzle", Some(Source::Weekly)), Err(Error::WeeklySource)));
+        assert!(matches!(add_source(PUZZLES, "Pizza", None), Ok(None)));
+        assert!(matches!(add_source(PUZZLES, "Pizza", Some(Source::Campaign)), Err(Error::SourceConflict)));
+        assert!(matches!(add_source(PUZZLES, "Test Puzzle*", None), Ok(None)));
+    }
+}
@@ -0,0 +1,73 @@
+use {
+    chrono::prelude::*,
+    itertools::Itertools as _,
+    omsim_rs::data::*,
+    crate::{
+        InOut,
+        molecules,
+        util::fnv1a,
+    },
+};
+
+/// Incremented whenever the catalogue changes. Included in API responses and exports, see [`crate::diff`] for comparing versions.
+pub(crate) const CATALOGUE_VERSION: u32 = 1;
+/// The [`catalogue_hash`] of `src/molecules.rs` at [`CATALOGUE_VERSION`]. A test checks this so the version can't be forgotten when the catalogue changes.
+pub(crate) const CATALOGUE_HASH: u64 = 0x28affb6ec39bf8de;
+
+/// A hash of the source code of the catalogue which ignores whitespace, so reformatting it or converting its line endings doesn't count as a change.
+pub(crate) fn catalogue_hash(source: &str) -> u64 {
+    fnv1a(&source.bytes().filter(|byte| !byte.is_ascii_whitespace()).collect_vec())
+}
+
+/// Shown wherever recent additions are listed, since earlier additions can't be dated.
+pub(crate) const STARTED_NOTE: &str = "The changelog was started on 2025-01-10. Molecules added before then aren't listed here, since their dates weren't recorded.";
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).expect("invalid date in changelog")
+}
+
+/// When each appearance was added to the catalogue, as the puzzle and the name of the molecule in it, most recent first.
+///
+/// Appearances which were added before this changelog was started aren't listed.
+pub(crate) fn changelog() -> Vec<(NaiveDate, &'static str, &'static str)> {
+    vec![
+        (date(2025, 1, 10), "Thermic Capacitor*", "Argentic Glass"),
+        (date(2025, 1, 10), "Thermic Capacitor*", "Elemental Copper"),
+        (date(2025, 1, 10), "Thermic Capacitor*", "Elemental Fire"),
+        (date(2025, 1, 10), "Thermic Capacitor*", "Elemental Quicksilver"),
+        (date(2025, 1, 10), "Thermic Capacitor*", "Heat-Preserving Component"),
+    ]
+}
+
+/// The date a catalogue entry was added, i.e. when its first appearance was added.
+/// Returns `None` if that was before the changelog was started.
+pub(crate) fn added(appearances: &[(&'static str, InOut, &'static str)], changelog: &[(NaiveDate, &'static str, &'static str)]) -> Option<NaiveDate> {
+    appearances.iter()
+        .map(|(puzzle, _, name)| changelog.iter().find(|(_, iter_puzzle, iter_name)| iter_puzzle == puzzle && iter_name == name).map(|(date, _, _)| *date))
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .min()
+}
+
+/// The most recently added catalogue entries, newest first.
+pub(crate) fn recent(limit: usize) -> Vec<(NaiveDate, Molecule, Vec<(&'static str, InOut, &'static str)>)> {
+    let changelog = changelog();
+    molecules::molecules().into_iter()
+        .filter_map(|(molecule, appearances)| Some((added(&appearances, &changelog)?, molecule, appearances)))
+        .sorted_by(|(date1, _, _), (date2, _, _)| date2.cmp(date1))
+        .take(limit)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_matches_catalogue() {
+        assert_eq!(
+            catalogue_hash(include_str!("molecules.rs")), CATALOGUE_HASH,
+            "the catalogue changed, increment CATALOGUE_VERSION and set CATALOGUE_HASH to the new hash (`molecule-db add-entry` does both)",
+        );
+    }
+}
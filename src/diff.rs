@@ -0,0 +1,171 @@
+use {
+    itertools::Itertools as _,
+    schemars::JsonSchema,
+    serde::{
+        Deserialize,
+        Serialize,
+    },
+    crate::{
+        MoleculeExt as _,
+        changelog::CATALOGUE_VERSION,
+        export,
+        molecules,
+    },
+};
+
+/// The parts of a catalogue JSON export needed for diffing. Atoms and bonds are ignored since they're determined by the molecule ID.
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Snapshot {
+    /// Exports from before catalogue versions were introduced don't have a version and are treated as version 0.
+    #[serde(default)]
+    version: u32,
+    molecules: Vec<SnapshotMolecule>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotMolecule {
+    id: String,
+    appearances: Vec<Appearance>,
+}
+
+#[derive(Clone, PartialEq, Eq, Deserialize, JsonSchema, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Appearance {
+    puzzle: String,
+    role: String,
+    name: String,
+}
+
+/// The request body of a catalogue diff: either a snapshot to compare to the current catalogue, or two snapshots to compare to each other.
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum DiffRequest {
+    Between {
+        from: Snapshot,
+        to: Snapshot,
+    },
+    Since(Snapshot),
+}
+
+impl DiffRequest {
+    pub(crate) fn diff(&self) -> Diff {
+        match self {
+            Self::Between { from, to } => diff(from, to),
+            Self::Since(from) => diff(from, &Snapshot::current()),
+        }
+    }
+}
+
+impl Snapshot {
+    pub(crate) fn current() -> Self {
+        Self {
+            version: CATALOGUE_VERSION,
+            molecules: molecules::molecules().into_iter().map(|(molecule, appearances)| SnapshotMolecule {
+                id: molecule.id(),
+                appearances: appearances.iter().map(|(puzzle, inout, name)| Appearance { puzzle: (*puzzle).to_owned(), role: export::role(inout).to_owned(), name: (*name).to_owned() }).collect(),
+            }).collect(),
+        }
+    }
+
+    fn molecule(&self, id: &str) -> Option<&SnapshotMolecule> {
+        self.molecules.iter().find(|molecule| molecule.id == id)
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MoleculeChange {
+    id: String,
+    appearances: Vec<Appearance>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct IdChange {
+    old_id: String,
+    new_id: String,
+    /// The appearances the old and new molecule have in common.
+    appearances: Vec<Appearance>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AppearanceChange {
+    id: String,
+    appearance: Appearance,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Rename {
+    id: String,
+    puzzle: String,
+    role: String,
+    old_name: String,
+    new_name: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Diff {
+    from_version: u32,
+    to_version: u32,
+    added_molecules: Vec<MoleculeChange>,
+    removed_molecules: Vec<MoleculeChange>,
+    /// Molecules which were removed and replaced with a different molecule under one of the same names, e.g. when a molecule's shape was corrected.
+    changed_ids: Vec<IdChange>,
+    /// New appearances of molecules which are in both versions.
+    added_appearances: Vec<AppearanceChange>,
+    /// Removed appearances of molecules which are in both versions.
+    removed_appearances: Vec<AppearanceChange>,
+    /// Appearances of molecules which are in both versions where only the name of the molecule changed.
+    renamed: Vec<Rename>,
+}
+
+pub(crate) fn diff(from: &Snapshot, to: &Snapshot) -> Diff {
+    let added = to.molecules.iter().filter(|molecule| from.molecule(&molecule.id).is_none()).collect_vec();
+    let removed = from.molecules.iter().filter(|molecule| to.molecule(&molecule.id).is_none()).collect_vec();
+    let mut changed_ids = Vec::default();
+    for old in &removed {
+        for new in &added {
+            let common = old.appearances.iter().filter(|appearance| new.appearances.contains(appearance)).cloned().collect_vec();
+            if !common.is_empty() {
+                changed_ids.push(IdChange { old_id: old.id.clone(), new_id: new.id.clone(), appearances: common });
+            }
+        }
+    }
+    let mut added_appearances = Vec::default();
+    let mut removed_appearances = Vec::default();
+    let mut renamed = Vec::default();
+    for new in &to.molecules {
+        let Some(old) = from.molecule(&new.id) else { continue };
+        let mut old_only = old.appearances.iter().filter(|appearance| !new.appearances.contains(appearance)).collect_vec();
+        for appearance in new.appearances.iter().filter(|appearance| !old.appearances.contains(appearance)) {
+            if let Some(idx) = old_only.iter().position(|old_appearance| old_appearance.puzzle == appearance.puzzle && old_appearance.role == appearance.role) {
+                let old_appearance = old_only.remove(idx);
+                renamed.push(Rename {
+                    id: new.id.clone(),
+                    puzzle: appearance.puzzle.clone(),
+                    role: appearance.role.clone(),
+                    old_name: old_appearance.name.clone(),
+                    new_name: appearance.name.clone(),
+                });
+            } else {
+                added_appearances.push(AppearanceChange { id: new.id.clone(), appearance: appearance.clone() });
+            }
+        }
+        removed_appearances.extend(old_only.into_iter().map(|appearance| AppearanceChange { id: new.id.clone(), appearance: appearance.clone() }));
+    }
+    Diff {
+        from_version: from.version,
+        to_version: to.version,
+        added_molecules: added.into_iter().map(|molecule| MoleculeChange { id: molecule.id.clone(), appearances: molecule.appearances.clone() }).collect(),
+        removed_molecules: removed.into_iter().map(|molecule| MoleculeChange { id: molecule.id.clone(), appearances: molecule.appearances.clone() }).collect(),
+        changed_ids,
+        added_appearances,
+        removed_appearances,
+        renamed,
+    }
+}
@@ -0,0 +1,272 @@
+use {
+    std::{
+        fmt::Write as _,
+        io,
+        path::Path,
+    },
+    chrono::NaiveDate,
+    enum_iterator::Sequence,
+    itertools::Itertools as _,
+    omsim_rs::data::*,
+    schemars::JsonSchema,
+    serde::Serialize,
+    crate::{
+        InOut,
+        MoleculeExt as _,
+        changelog,
+        format_atom,
+        molecules,
+        puzzles::{
+            self,
+            Source,
+        },
+    },
+};
+
+/// The schema of the SQLite export. Every molecule has a row in `molecules`, every atom, bond, and appearance references its molecule by ID.
+pub(crate) const SQLITE_SCHEMA: &str = "
+-- one row per known molecule; id is the stable molecule ID also used in URLs
+CREATE TABLE molecules (id TEXT PRIMARY KEY NOT NULL, atom_count INTEGER NOT NULL, bond_count INTEGER NOT NULL);
+-- q and r are axial hex coordinates, atom is a lowercase element name like 'salt' or 'quicksilver'
+CREATE TABLE atoms (molecule_id TEXT NOT NULL REFERENCES molecules (id), q INTEGER NOT NULL, r INTEGER NOT NULL, atom TEXT NOT NULL, PRIMARY KEY (molecule_id, q, r));
+-- type is 'normal' or 'triplex'; red, black, and yellow are 0 or 1 and only set for triplex bonds
+CREATE TABLE bonds (molecule_id TEXT NOT NULL REFERENCES molecules (id), start_q INTEGER NOT NULL, start_r INTEGER NOT NULL, end_q INTEGER NOT NULL, end_r INTEGER NOT NULL, type TEXT NOT NULL, red INTEGER NOT NULL, black INTEGER NOT NULL, yellow INTEGER NOT NULL);
+-- one row per puzzle; source is 'campaign' (the game's main campaign), 'journal' (the game's journal), 'tournament' (community tournaments), 'weekly' (community weeklies, whose names end in '*'), or NULL if unknown
+CREATE TABLE puzzles (name TEXT PRIMARY KEY NOT NULL, source TEXT);
+-- role is 'reagent', 'product', or 'both'; name is what the puzzle calls the molecule
+CREATE TABLE appearances (molecule_id TEXT NOT NULL REFERENCES molecules (id), puzzle TEXT NOT NULL REFERENCES puzzles (name), role TEXT NOT NULL, name TEXT NOT NULL);
+";
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error(transparent)] Io(#[from] io::Error),
+    #[error(transparent)] Sql(#[from] rusqlite::Error),
+}
+
+pub(crate) fn role(inout: &InOut) -> &'static str {
+    match inout {
+        InOut::Reagent => "reagent",
+        InOut::Product => "product",
+        InOut::Both => "both",
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AtomJson {
+    pub(crate) q: i32,
+    pub(crate) r: i32,
+    pub(crate) atom: &'static str,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BondJson {
+    pub(crate) from: [i32; 2],
+    pub(crate) to: [i32; 2],
+    /// `n` for a normal bond, otherwise a combination of `r`, `k`, and `y` for the colors of a triplex bond, like in the builder state.
+    #[serde(rename = "type")]
+    pub(crate) ty: String,
+}
+
+pub(crate) fn bond_type_code(ty: BondType) -> String {
+    match ty {
+        BondType::Normal => "n".to_owned(),
+        BondType::Triplex { red, black, yellow } => format!("{}{}{}", if red { "r" } else { "" }, if black { "k" } else { "" }, if yellow { "y" } else { "" }),
+    }
+}
+
+pub(crate) fn atoms_json(molecule: &Molecule) -> Vec<AtomJson> {
+    molecule.atoms.iter()
+        .sorted_by_key(|(pos, _)| (pos.q, pos.r))
+        .map(|(pos, &atom)| AtomJson { q: pos.q, r: pos.r, atom: format_atom(atom) })
+        .collect()
+}
+
+pub(crate) fn bonds_json(molecule: &Molecule) -> Vec<BondJson> {
+    molecule.bonds.iter()
+        .sorted_by_key(|bond| (bond.start.q, bond.start.r, bond.end.q, bond.end.r))
+        .map(|bond| BondJson { from: [bond.start.q, bond.start.r], to: [bond.end.q, bond.end.r], ty: bond_type_code(bond.ty) })
+        .collect()
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct AppearanceJson {
+    puzzle: &'static str,
+    role: &'static str,
+    name: &'static str,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct MoleculeJson {
+    id: String,
+    /// When this entry was added to the catalogue, or `None` if that was before the changelog was started.
+    added: Option<NaiveDate>,
+    atoms: Vec<AtomJson>,
+    bonds: Vec<BondJson>,
+    appearances: Vec<AppearanceJson>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct PuzzleJson {
+    name: &'static str,
+    source: Option<Source>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CatalogueJson {
+    version: u32,
+    molecules: Vec<MoleculeJson>,
+    puzzles: Vec<PuzzleJson>,
+}
+
+pub(crate) fn json() -> CatalogueJson {
+    let changelog = changelog::changelog();
+    CatalogueJson {
+        version: changelog::CATALOGUE_VERSION,
+        molecules: molecules::molecules().into_iter().map(|(molecule, appearances)| MoleculeJson {
+            id: molecule.id(),
+            added: changelog::added(&appearances, &changelog),
+            atoms: atoms_json(&molecule),
+            bonds: bonds_json(&molecule),
+            appearances: appearances.iter().map(|(puzzle, inout, name)| AppearanceJson { puzzle: *puzzle, role: role(inout), name: *name }).collect(),
+        }).collect(),
+        puzzles: puzzles::puzzles().into_iter().map(|puzzle| PuzzleJson { name: puzzle.name, source: puzzle.source }).collect(),
+    }
+}
+
+#[derive(Clone, Copy, Sequence)]
+pub(crate) enum CsvTable {
+    Molecules,
+    Atoms,
+    Bonds,
+    Puzzles,
+    Appearances,
+}
+
+impl CsvTable {
+    pub(crate) fn file_name(&self) -> &'static str {
+        match self {
+            Self::Molecules => "molecules.csv",
+            Self::Atoms => "atoms.csv",
+            Self::Bonds => "bonds.csv",
+            Self::Puzzles => "puzzles.csv",
+            Self::Appearances => "appearances.csv",
+        }
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// One table of the catalogue, with the same columns as the corresponding table in [`SQLITE_SCHEMA`].
+pub(crate) fn csv(table: CsvTable) -> String {
+    let mut csv = String::default();
+    match table {
+        CsvTable::Molecules => {
+            csv.push_str("id,atom_count,bond_count\n");
+            for (molecule, _) in molecules::molecules() {
+                writeln!(csv, "{},{},{}", molecule.id(), molecule.atoms.len(), molecule.bonds.len()).expect("writing to a String can't fail");
+            }
+        }
+        CsvTable::Atoms => {
+            csv.push_str("molecule_id,q,r,atom\n");
+            for (molecule, _) in molecules::molecules() {
+                let id = molecule.id();
+                for AtomJson { q, r, atom } in atoms_json(&molecule) {
+                    writeln!(csv, "{id},{q},{r},{atom}").expect("writing to a String can't fail");
+                }
+            }
+        }
+        CsvTable::Bonds => {
+            csv.push_str("molecule_id,start_q,start_r,end_q,end_r,type,red,black,yellow\n");
+            for (molecule, _) in molecules::molecules() {
+                let id = molecule.id();
+                for bond in molecule.bonds.iter().sorted_by_key(|bond| (bond.start.q, bond.start.r, bond.end.q, bond.end.r)) {
+                    let (ty, red, black, yellow) = match bond.ty {
+                        BondType::Normal => ("normal", false, false, false),
+                        BondType::Triplex { red, black, yellow } => ("triplex", red, black, yellow),
+                    };
+                    writeln!(csv, "{id},{},{},{},{},{ty},{},{},{}", bond.start.q, bond.start.r, bond.end.q, bond.end.r, u8::from(red), u8::from(black), u8::from(yellow)).expect("writing to a String can't fail");
+                }
+            }
+        }
+        CsvTable::Puzzles => {
+            csv.push_str("name,source\n");
+            for puzzle in puzzles::puzzles() {
+                writeln!(csv, "{},{}", csv_field(puzzle.name), puzzle.source.map_or("", |source| source.as_str())).expect("writing to a String can't fail");
+            }
+        }
+        CsvTable::Appearances => {
+            csv.push_str("molecule_id,puzzle,role,name\n");
+            for (molecule, appearances) in molecules::molecules() {
+                let id = molecule.id();
+                for (puzzle, inout, name) in &appearances {
+                    writeln!(csv, "{id},{},{},{}", csv_field(puzzle), role(inout), csv_field(name)).expect("writing to a String can't fail");
+                }
+            }
+        }
+    }
+    csv
+}
+
+/// Writes the catalogue to a new SQLite database at the given path, replacing any existing file. See [`SQLITE_SCHEMA`].
+pub(crate) fn sqlite(path: &Path) -> Result<(), Error> {
+    match std::fs::remove_file(path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    fill(&mut rusqlite::Connection::open(path)?)
+}
+
+fn fill(db: &mut rusqlite::Connection) -> Result<(), Error> {
+    let transaction = db.transaction()?;
+    transaction.execute_batch(SQLITE_SCHEMA)?;
+    for puzzle in puzzles::puzzles() {
+        transaction.execute("INSERT INTO puzzles (name, source) VALUES (?1, ?2)", (puzzle.name, puzzle.source.map(|source| source.as_str())))?;
+    }
+    for (molecule, appearances) in molecules::molecules() {
+        let id = molecule.id();
+        transaction.execute("INSERT INTO molecules (id, atom_count, bond_count) VALUES (?1, ?2, ?3)", (&id, molecule.atoms.len(), molecule.bonds.len()))?;
+        for AtomJson { q, r, atom } in atoms_json(&molecule) {
+            transaction.execute("INSERT INTO atoms (molecule_id, q, r, atom) VALUES (?1, ?2, ?3, ?4)", (&id, q, r, atom))?;
+        }
+        for bond in &molecule.bonds {
+            let (ty, red, black, yellow) = match bond.ty {
+                BondType::Normal => ("normal", false, false, false),
+                BondType::Triplex { red, black, yellow } => ("triplex", red, black, yellow),
+            };
+            transaction.execute(
+                "INSERT INTO bonds (molecule_id, start_q, start_r, end_q, end_r, type, red, black, yellow) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                (&id, bond.start.q, bond.start.r, bond.end.q, bond.end.r, ty, red, black, yellow),
+            )?;
+        }
+        for (puzzle, inout, name) in &appearances {
+            transaction.execute("INSERT INTO appearances (molecule_id, puzzle, role, name) VALUES (?1, ?2, ?3, ?4)", (&id, puzzle, role(inout), name))?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+/// The SQLite export served by the web app. Since the catalogue is compiled in, it's generated once at startup rather than per request.
+pub(crate) struct SqliteExport(pub(crate) Vec<u8>);
+
+impl SqliteExport {
+    /// Generates the SQLite export in memory and serializes it.
+    pub(crate) fn generate() -> Result<Self, Error> {
+        let mut db = rusqlite::Connection::open_in_memory()?;
+        fill(&mut db)?;
+        Ok(Self(db.serialize(rusqlite::DatabaseName::Main)?.to_vec()))
+    }
+}
@@ -0,0 +1,235 @@
+use {
+    enum_iterator::{
+        Sequence,
+        all,
+    },
+    itertools::Itertools as _,
+    omsim_rs::data::*,
+    schemars::JsonSchema,
+    serde::Serialize,
+    crate::{
+        format_atom,
+        puzzles::Puzzle,
+        ratios,
+    },
+};
+
+/// All atom types in the order they're listed in atom balances.
+pub(crate) const ATOMS: [Atom; 15] = [
+    Atom::Salt,
+    Atom::Air,
+    Atom::Earth,
+    Atom::Fire,
+    Atom::Water,
+    Atom::Quicksilver,
+    Atom::Lead,
+    Atom::Tin,
+    Atom::Iron,
+    Atom::Copper,
+    Atom::Silver,
+    Atom::Gold,
+    Atom::Vitae,
+    Atom::Mors,
+    Atom::Quintessence,
+];
+
+pub(crate) const CARDINALS: [Atom; 4] = [Atom::Air, Atom::Earth, Atom::Fire, Atom::Water];
+
+/// Metals in order of increasing nobility.
+pub(crate) const METALS: [Atom; 6] = [Atom::Lead, Atom::Tin, Atom::Iron, Atom::Copper, Atom::Silver, Atom::Gold];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence, JsonSchema, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum Glyph {
+    Calcification,
+    /// Turns a salt atom into a copy of an adjacent cardinal atom.
+    Duplication,
+    Projection,
+    Purification,
+    Animismus,
+    /// Removes atoms which aren't needed for the products.
+    Disposal,
+    Dispersion,
+    Unification,
+    TriplexBonding,
+}
+
+impl Glyph {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Calcification => "Glyph of Calcification",
+            Self::Duplication => "Glyph of Duplication",
+            Self::Projection => "Glyph of Projection",
+            Self::Purification => "Glyph of Purification",
+            Self::Animismus => "Glyph of Animismus",
+            Self::Disposal => "Glyph of Disposal",
+            Self::Dispersion => "Glyph of Dispersion",
+            Self::Unification => "Glyph of Unification",
+            Self::TriplexBonding => "Glyph of Triplex Bonding",
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GlyphAnalysis {
+    /// For each atom type, the number of atoms in one of each product minus the number of atoms in one of each reagent.
+    pub(crate) balance: Vec<(&'static str, isize)>,
+    /// Glyphs without which some atom type or bond in the products can't be produced.
+    pub(crate) necessary: Vec<Glyph>,
+    /// A minimal set of glyphs with which every atom type and bond in the products can be produced, or `None` if that's impossible.
+    pub(crate) sufficient: Option<Vec<Glyph>>,
+}
+
+/// The atom types which can be produced from the given ones using the given glyphs.
+fn closure(mut available: Vec<Atom>, glyphs: &[Glyph]) -> Vec<Atom> {
+    loop {
+        let mut new = Vec::default();
+        for glyph in glyphs {
+            match glyph {
+                Glyph::Calcification => if CARDINALS.iter().any(|atom| available.contains(atom)) {
+                    new.push(Atom::Salt);
+                },
+                Glyph::Projection => if available.contains(&Atom::Quicksilver) {
+                    new.extend(METALS.windows(2).filter(|pair| available.contains(&pair[0])).map(|pair| pair[1]));
+                },
+                Glyph::Purification => new.extend(METALS.windows(2).filter(|pair| available.contains(&pair[0])).map(|pair| pair[1])),
+                Glyph::Animismus => if available.contains(&Atom::Salt) {
+                    new.push(Atom::Vitae);
+                    new.push(Atom::Mors);
+                },
+                Glyph::Dispersion => if available.contains(&Atom::Quintessence) {
+                    new.extend(CARDINALS);
+                },
+                Glyph::Unification => if CARDINALS.iter().all(|atom| available.contains(atom)) {
+                    new.push(Atom::Quintessence);
+                },
+                // duplication only makes more of a cardinal which is already there
+                Glyph::Duplication | Glyph::Disposal | Glyph::TriplexBonding => {}
+            }
+        }
+        new.retain(|atom| !available.contains(atom));
+        if new.is_empty() {
+            break available
+        }
+        available.extend(new);
+    }
+}
+
+/// The atom types which can be used up, either by being part of a product or by turning them into usable atoms (or disposing of them) with the given glyphs.
+fn usable(available: &[Atom], products: &[Atom], glyphs: &[Glyph]) -> Vec<Atom> {
+    let reactions = glyphs.iter().flat_map(|&glyph| ratios::reactions(glyph)).collect_vec();
+    let mut usable = products.to_vec();
+    loop {
+        let new = available.iter()
+            .copied()
+            .filter(|atom| !usable.contains(atom))
+            .filter(|atom| reactions.iter().any(|reaction|
+                reaction.consumed.contains(atom)
+                && reaction.consumed.iter().all(|consumed| available.contains(consumed))
+                && reaction.produced.iter().all(|produced| usable.contains(produced))
+            ))
+            .collect_vec();
+        if new.is_empty() {
+            break usable
+        }
+        usable.extend(new);
+    }
+}
+
+fn has_triplex_bonds(molecule: &Molecule) -> bool {
+    molecule.bonds.iter().any(|bond| matches!(bond.ty, BondType::Triplex { .. }))
+}
+
+/// Checks whether the products of the puzzle can be produced from its reagents using the given glyphs.
+///
+/// This only considers which atom types and bond kinds can be produced, not how many of them. Since every atom of a reagent which is used has to end up somewhere, reagents with atoms which can't be used up are left out.
+fn is_sufficient(puzzle: &Puzzle, glyphs: &[Glyph]) -> bool {
+    let product_atoms = puzzle.products.iter().flat_map(|(molecule, _)| molecule.atoms.values().copied()).filter(|&atom| atom != Atom::Repeat).unique().collect_vec();
+    let mut reagents = puzzle.reagents.iter().map(|(molecule, _)| molecule).collect_vec();
+    let available = loop {
+        let available = closure(reagents.iter().flat_map(|molecule| molecule.atoms.values().copied()).unique().collect(), glyphs);
+        let usable = usable(&available, &product_atoms, glyphs);
+        let before = reagents.len();
+        reagents.retain(|molecule| molecule.atoms.values().all(|atom| usable.contains(atom)));
+        if reagents.len() == before {
+            break available
+        }
+    };
+    let needs_triplex = puzzle.products.iter().any(|(molecule, _)| has_triplex_bonds(molecule)) && !reagents.iter().any(|molecule| has_triplex_bonds(molecule));
+    product_atoms.iter().all(|atom| available.contains(atom))
+    && (!needs_triplex || (glyphs.contains(&Glyph::TriplexBonding) && available.contains(&Atom::Fire)))
+}
+
+fn count(molecules: &[(Molecule, &'static str)], atom: Atom) -> isize {
+    molecules.iter().map(|(molecule, _)| molecule.atoms.values().filter(|&&iter_atom| iter_atom == atom).count() as isize).sum()
+}
+
+pub(crate) fn analyze(puzzle: &Puzzle) -> GlyphAnalysis {
+    let balance = ATOMS.into_iter()
+        .map(|atom| (format_atom(atom), count(&puzzle.products, atom) - count(&puzzle.reagents, atom)))
+        .filter(|&(_, difference)| difference != 0)
+        .collect();
+    let all_glyphs = all::<Glyph>().collect::<Vec<_>>();
+    // if there is a ratio at all, the glyphs also have to be able to balance the atom counts, e.g. duplication is needed to turn spare salt into cardinals
+    let balanced = ratios::minimal_ratio(puzzle, &all_glyphs).is_some();
+    let is_sufficient = |glyphs: &[Glyph]| is_sufficient(puzzle, glyphs) && (!balanced || ratios::minimal_ratio(puzzle, glyphs).is_some());
+    if !is_sufficient(&all_glyphs) {
+        return GlyphAnalysis { balance, necessary: Vec::default(), sufficient: None }
+    }
+    let necessary = all_glyphs.iter()
+        .copied()
+        .filter(|&glyph| !is_sufficient(&all_glyphs.iter().copied().filter(|&iter_glyph| iter_glyph != glyph).collect::<Vec<_>>()))
+        .collect();
+    let mut sufficient = all_glyphs;
+    // try to do without disposal first, so atoms are transformed rather than thrown away where possible
+    for glyph in all::<Glyph>().sorted_by_key(|&glyph| glyph != Glyph::Disposal) {
+        let without = sufficient.iter().copied().filter(|&iter_glyph| iter_glyph != glyph).collect::<Vec<_>>();
+        if is_sufficient(&without) {
+            sufficient = without;
+        }
+    }
+    GlyphAnalysis { balance, necessary, sufficient: Some(sufficient) }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::puzzles,
+        super::*,
+    };
+
+    fn puzzle(name: &str) -> Puzzle {
+        puzzles::puzzle(name).expect("puzzle not in catalogue")
+    }
+
+    #[test]
+    fn calcification() {
+        let analysis = analyze(&puzzle("Lesson: Introduction"));
+        assert_eq!(analysis.necessary, [Glyph::Calcification]);
+        assert_eq!(analysis.sufficient, Some(vec![Glyph::Calcification]));
+    }
+
+    #[test]
+    fn duplication() {
+        // the salt in each reagent has to become earth
+        let viscous_sludge = puzzle("Viscous Sludge");
+        assert!(!is_sufficient(&viscous_sludge, &[]));
+        assert!(is_sufficient(&viscous_sludge, &[Glyph::Duplication]));
+        assert!(is_sufficient(&viscous_sludge, &[Glyph::Disposal]));
+        assert_eq!(analyze(&viscous_sludge).sufficient, Some(vec![Glyph::Duplication]));
+        // animismus alone could make vitae and mors, but then there would be salt left over
+        let vapor_of_levity = analyze(&puzzle("Vapor of Levity"));
+        assert_eq!(vapor_of_levity.necessary, [Glyph::Animismus]);
+        assert_eq!(vapor_of_levity.sufficient, Some(vec![Glyph::Duplication, Glyph::Animismus]));
+    }
+
+    #[test]
+    fn purification() {
+        // the iron has to be purified into silver, the stabilized water goes into the product as is
+        let silver_paint = puzzle("Silver Paint");
+        assert!(is_sufficient(&silver_paint, &[Glyph::Purification]));
+        assert!(!is_sufficient(&silver_paint, &[Glyph::Calcification]));
+        assert!(is_sufficient(&silver_paint, &all::<Glyph>().collect::<Vec<_>>()));
+    }
+}
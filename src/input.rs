@@ -0,0 +1,161 @@
+use {
+    std::collections::{
+        HashMap,
+        HashSet,
+    },
+    omsim_rs::data::*,
+    schemars::JsonSchema,
+    serde::{
+        Deserialize,
+        Deserializer,
+        de::Error as _,
+    },
+    serde_json::Value,
+    crate::{
+        JsState,
+        parse_atom,
+        query::{
+            AtomPattern,
+            Query,
+        },
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error(transparent)] Json(#[from] serde_json::Error),
+    #[error("expected a string in text notation or an object")]
+    Type,
+    #[error("invalid text notation")]
+    Notation,
+    #[error("invalid builder state")]
+    State,
+    #[error("the molecule has no atoms")]
+    Empty,
+    #[error("there is more than one atom at {q},{r}")]
+    DuplicateAtom {
+        q: i32,
+        r: i32,
+    },
+    #[error("the bond from {},{} to {},{} doesn't connect adjacent positions", from[0], from[1], to[0], to[1])]
+    NotAdjacent {
+        from: [i32; 2],
+        to: [i32; 2],
+    },
+    #[error("there is a bond at {q},{r} but no atom")]
+    MissingAtom {
+        q: i32,
+        r: i32,
+    },
+    #[error("there is more than one bond between {},{} and {},{}", from[0], from[1], to[0], to[1])]
+    DuplicateBond {
+        from: [i32; 2],
+        to: [i32; 2],
+    },
+}
+
+fn deserialize_atom<'de, D: Deserializer<'de>>(deserializer: D) -> Result<AtomPattern, D::Error> {
+    let atom = String::deserialize(deserializer)?;
+    parse_atom(&atom).ok_or_else(|| D::Error::custom(format!("unknown atom type {atom:?}")))
+}
+
+fn deserialize_bond_type<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BondType, D::Error> {
+    let ty = String::deserialize(deserializer)?;
+    if ty == "n" {
+        Ok(BondType::Normal)
+    } else if !ty.is_empty() && ty.chars().all(|c| matches!(c, 'r' | 'k' | 'y')) {
+        Ok(BondType::Triplex { red: ty.contains('r'), black: ty.contains('k'), yellow: ty.contains('y') })
+    } else {
+        Err(D::Error::custom(format!("invalid bond type {ty:?}, expected \"n\" or a combination of \"r\", \"k\", and \"y\"")))
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct AtomInput {
+    q: i32,
+    r: i32,
+    /// An atom type like `salt`, or a wildcard like `metal`.
+    #[serde(deserialize_with = "deserialize_atom")]
+    #[schemars(with = "String")]
+    atom: AtomPattern,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct BondInput {
+    from: [i32; 2],
+    to: [i32; 2],
+    /// `n` for a normal bond, otherwise a combination of `r`, `k`, and `y` for the colors of a triplex bond.
+    #[serde(rename = "type", deserialize_with = "deserialize_bond_type")]
+    #[schemars(with = "String")]
+    ty: BondType,
+}
+
+/// A molecule given as lists of atoms and bonds, in the same format as the `canonical` field of lookup responses.
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub(crate) struct StructuredMolecule {
+    atoms: Vec<AtomInput>,
+    #[serde(default)]
+    bonds: Vec<BondInput>,
+}
+
+impl StructuredMolecule {
+    pub(crate) fn into_query(self) -> Result<Query, Error> {
+        if self.atoms.is_empty() {
+            return Err(Error::Empty)
+        }
+        let mut atoms = HashMap::default();
+        for AtomInput { q, r, atom } in self.atoms {
+            if atoms.insert(HexIndex { q, r }, atom).is_some() {
+                return Err(Error::DuplicateAtom { q, r })
+            }
+        }
+        let mut bonds = HashSet::<Bond>::default();
+        for BondInput { from, to, ty } in self.bonds {
+            for [q, r] in [from, to] {
+                if !atoms.contains_key(&HexIndex { q, r }) {
+                    return Err(Error::MissingAtom { q, r })
+                }
+            }
+            // same orientation as the bond keys in the builder state
+            let (start, end) = match [to[0] - from[0], to[1] - from[1]] {
+                [1, 0] | [-1, 1] | [0, 1] => (from, to),
+                [-1, 0] | [1, -1] | [0, -1] => (to, from),
+                _ => return Err(Error::NotAdjacent { from, to }),
+            };
+            let start = HexIndex { q: start[0], r: start[1] };
+            let end = HexIndex { q: end[0], r: end[1] };
+            if bonds.iter().any(|bond| bond.start == start && bond.end == end) {
+                return Err(Error::DuplicateBond { from, to })
+            }
+            bonds.insert(Bond { start, end, ty });
+        }
+        Ok(Query { atoms, bonds })
+    }
+}
+
+/// The formats accepted by [`query_from_json`]. Only used to describe the API, see [`crate::openapi`].
+#[derive(JsonSchema)]
+#[serde(untagged)]
+#[allow(unused)]
+pub(crate) enum MoleculeInput {
+    /// The text notation, see [`crate::MoleculeExt::notation`].
+    Notation(String),
+    Structured(StructuredMolecule),
+    State(JsState),
+}
+
+/// Parses a molecule given as a string in text notation (see [`crate::MoleculeExt::notation`]), a [`StructuredMolecule`], or a builder state.
+pub(crate) fn query_from_json(value: Value) -> Result<Query, Error> {
+    match value {
+        Value::String(notation) => JsState::from_notation(&notation).and_then(JsState::into_query).map_err(|_| Error::Notation),
+        Value::Object(object) => if object.contains_key("atoms") {
+            serde_json::from_value::<StructuredMolecule>(Value::Object(object))?.into_query()
+        } else {
+            serde_json::from_value::<JsState>(Value::Object(object))?.into_query().map_err(|_| Error::State)
+        },
+        _ => Err(Error::Type),
+    }
+}
@@ -0,0 +1,76 @@
+use {
+    std::collections::HashMap,
+    itertools::Itertools as _,
+    omsim_rs::data::*,
+    crate::{
+        InOut,
+        MoleculeExt as _,
+        molecules,
+    },
+};
+
+pub(crate) struct Entry {
+    pub(crate) molecule: Molecule,
+    pub(crate) id: String,
+    /// See [`crate::MoleculeExt::shape`].
+    pub(crate) shape: Molecule,
+    pub(crate) appearances: Vec<(&'static str, InOut, &'static str)>,
+}
+
+/// The catalogue along with the data lookups need about each entry, computed once at startup since the catalogue is compiled in.
+pub(crate) struct KnownMolecules {
+    pub(crate) entries: Vec<Entry>,
+    by_id: HashMap<String, usize>,
+    by_name: HashMap<&'static str, Vec<usize>>,
+    shape_groups: Vec<Vec<usize>>,
+}
+
+impl KnownMolecules {
+    pub(crate) fn compute() -> Self {
+        let entries = molecules::molecules().into_iter().map(|(molecule, appearances)| Entry {
+            id: molecule.id(),
+            shape: molecule.shape(),
+            molecule, appearances,
+        }).collect_vec();
+        let by_id = entries.iter().enumerate().map(|(idx, entry)| (entry.id.clone(), idx)).collect();
+        let mut by_name = HashMap::<_, Vec<_>>::default();
+        for (idx, entry) in entries.iter().enumerate() {
+            for name in entry.appearances.iter().map(|(_, _, name)| *name).unique() {
+                by_name.entry(name).or_default().push(idx);
+            }
+        }
+        let mut groups = HashMap::<String, Vec<usize>>::default();
+        for (idx, entry) in entries.iter().enumerate() {
+            groups.entry(entry.shape.id()).or_default().push(idx);
+        }
+        let shape_groups = groups.into_values()
+            .filter(|group| group.len() > 1)
+            .sorted_unstable_by_key(|group| group.iter().flat_map(|&idx| entries[idx].appearances.iter().map(|(_, _, name)| *name)).min())
+            .collect();
+        Self { entries, by_id, by_name, shape_groups }
+    }
+
+    /// The catalogue entry for the given normalized molecule.
+    pub(crate) fn get(&self, molecule: &Molecule) -> Option<&Entry> {
+        self.by_id.get(&molecule.id()).map(|&idx| &self.entries[idx]).filter(|entry| entry.molecule == *molecule)
+    }
+
+    /// The catalogue entries with the given [`crate::MoleculeExt::id`] or name. Different molecules can have the same name, so there may be more than one.
+    pub(crate) fn find(&self, id_or_name: &str) -> Vec<&Entry> {
+        if let Some(&idx) = self.by_id.get(id_or_name) {
+            vec![&self.entries[idx]]
+        } else {
+            self.by_name.get(id_or_name).into_iter().flatten().map(|&idx| &self.entries[idx]).collect()
+        }
+    }
+
+    /// Like [`KnownMolecules::find`], but only if the ID or name is unambiguous.
+    pub(crate) fn find_unique(&self, id_or_name: &str) -> Option<&Entry> {
+        self.find(id_or_name).into_iter().exactly_one().ok()
+    }
+
+    /// All known molecules grouped by their shape, skipping shapes which only occur once.
+    pub(crate) fn shape_groups(&self) -> impl Iterator<Item = impl Iterator<Item = &Entry> + '_> + '_ {
+        self.shape_groups.iter().map(|group| group.iter().map(|&idx| &self.entries[idx]))
+    }
+}
@@ -0,0 +1,313 @@
+use {
+    std::{
+        collections::{
+            BTreeSet,
+            HashMap,
+        },
+        io,
+        net::IpAddr,
+        sync::Mutex,
+        time::Instant,
+    },
+    rocket::{
+        Data,
+        Request,
+        data::{
+            self,
+            ByteUnit,
+            FromData,
+            ToByteUnit as _,
+        },
+        http::Status,
+        outcome::Outcome,
+        request::{
+            self,
+            FromRequest,
+        },
+        serde::json,
+    },
+    serde::de::DeserializeOwned,
+    crate::query::Query,
+};
+
+/// The name of the data limit for lookup request bodies, configurable like Rocket's own limits, e.g. `ROCKET_LIMITS={lookup="16KiB"}`.
+pub(crate) const LOOKUP_LIMIT_NAME: &str = "lookup";
+
+pub(crate) fn default_lookup_limit() -> ByteUnit {
+    64.kibibytes()
+}
+
+/// The name of the data limit for catalogue diff request bodies, which are entire catalogue exports.
+pub(crate) const DIFF_LIMIT_NAME: &str = "catalogue-diff";
+
+pub(crate) fn default_diff_limit() -> ByteUnit {
+    4.mebibytes()
+}
+
+/// Routes which are subject to the [`RateLimiter`] and whose request bodies are size limited.
+pub(crate) const LIMITED_ROUTES: [&str; 7] = [
+    "batch_lookup",
+    "catalogue_diff",
+    "lookup",
+    "lookup_v2",
+    "molecule_from_state",
+    "sandbox_puzzle_from_state",
+    "submit_molecule",
+];
+
+/// Lookups normalize the molecule and compare it against the entire catalogue, so they're limited to molecules of a reasonable size, and batch lookups to a reasonable number of molecules.
+pub(crate) struct MoleculeLimits {
+    pub(crate) max_atoms: usize,
+    pub(crate) max_bonds: usize,
+    pub(crate) max_batch: usize,
+}
+
+impl MoleculeLimits {
+    pub(crate) const DEFAULT_MAX_ATOMS: usize = 256;
+    pub(crate) const DEFAULT_MAX_BONDS: usize = 512;
+    pub(crate) const DEFAULT_MAX_BATCH: usize = 100;
+
+    pub(crate) fn check_batch(&self, len: usize) -> Result<(), TooLarge> {
+        if len > self.max_batch {
+            Err(TooLarge::Batch { max: self.max_batch })
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn check(&self, query: &Query) -> Result<(), TooLarge> {
+        if query.atoms.len() > self.max_atoms {
+            Err(TooLarge::Atoms { max: self.max_atoms })
+        } else if query.bonds.len() > self.max_bonds {
+            Err(TooLarge::Bonds { max: self.max_bonds })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum TooLarge {
+    #[error("the molecule has more than {max} atoms")]
+    Atoms {
+        max: usize,
+    },
+    #[error("the molecule has more than {max} bonds")]
+    Bonds {
+        max: usize,
+    },
+    #[error("the batch has more than {max} molecules")]
+    Batch {
+        max: usize,
+    },
+}
+
+/// A token bucket per client IP address, refilled at the configured number of requests per minute. Disabled if there is no limit.
+pub(crate) struct RateLimiter {
+    limit: Option<RateLimit>,
+    trust_ip_header: bool,
+}
+
+struct RateLimit {
+    per_minute: u32,
+    buckets: Mutex<Buckets>,
+}
+
+/// The buckets of the most recently seen clients, bounded by evicting the least recently seen one.
+/// That client's bucket is the most likely to have refilled completely, making it indistinguishable from a new one.
+#[derive(Default)]
+struct Buckets {
+    by_client: HashMap<IpAddr, (f64, Instant)>,
+    by_update: BTreeSet<(Instant, IpAddr)>,
+}
+
+impl Buckets {
+    const MAX_CLIENTS: usize = 10_000;
+}
+
+impl RateLimiter {
+    /// If `trust_ip_header` is set, clients are told apart by Rocket's `ip_header`, which must only be enabled behind a reverse proxy that sets it. Otherwise, the address of the remote end of the connection is used.
+    pub(crate) fn new(per_minute: Option<u32>, trust_ip_header: bool) -> Self {
+        Self {
+            limit: per_minute.filter(|&per_minute| per_minute > 0).map(|per_minute| RateLimit { per_minute, buckets: Mutex::default() }),
+            trust_ip_header,
+        }
+    }
+
+    fn client(&self, req: &Request<'_>) -> Option<IpAddr> {
+        if self.trust_ip_header {
+            req.client_ip()
+        } else {
+            req.remote().map(|remote| remote.ip())
+        }
+    }
+
+    /// Takes a token from the client's bucket. If it's empty, returns the number of seconds until the next token is available.
+    fn take(&self, client: IpAddr) -> Result<(), u64> {
+        let Some(ref limit) = self.limit else { return Ok(()) };
+        let capacity = f64::from(limit.per_minute);
+        let per_second = capacity / 60.0;
+        let now = Instant::now();
+        let mut buckets = limit.buckets.lock().expect("rate limiter poisoned");
+        let Buckets { ref mut by_client, ref mut by_update } = *buckets;
+        let mut tokens = if let Some(&(tokens, updated)) = by_client.get(&client) {
+            by_update.remove(&(updated, client));
+            (tokens + now.duration_since(updated).as_secs_f64() * per_second).min(capacity)
+        } else {
+            if by_client.len() >= Buckets::MAX_CLIENTS {
+                if let Some((_, evicted)) = by_update.pop_first() {
+                    by_client.remove(&evicted);
+                }
+            }
+            capacity
+        };
+        let result = if tokens >= 1.0 {
+            tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - tokens) / per_second).ceil() as u64)
+        };
+        by_client.insert(client, (tokens, now));
+        by_update.insert((now, client));
+        result
+    }
+}
+
+/// The number of seconds after which a rate limited client may retry, for the `Retry-After` header of the 429 response.
+pub(crate) struct RetryAfter(pub(crate) u64);
+
+fn check(req: &Request<'_>, limiter: Option<&RateLimiter>) -> request::Outcome<(), ()> {
+    let Some(limiter) = limiter else { return Outcome::Success(()) };
+    let Some(client) = limiter.client(req) else { return Outcome::Success(()) };
+    match limiter.take(client) {
+        Ok(()) => Outcome::Success(()),
+        Err(retry_after) => {
+            req.local_cache(|| RetryAfter(retry_after));
+            Outcome::Error((Status::TooManyRequests, ()))
+        }
+    }
+}
+
+/// Request guard for lookups and other requests with client-provided bodies, which fails with 429 Too Many Requests if the client has exceeded the rate limit.
+pub(crate) struct RateLimited;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RateLimited {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, ()> {
+        check(req, req.rocket().state::<RateLimiter>()).map(|()| Self)
+    }
+}
+
+/// Admin login attempts are always rate limited, independently of the configured lookup rate limit, so the admin token can't be guessed by brute force.
+pub(crate) struct LoginRateLimiter(RateLimiter);
+
+impl LoginRateLimiter {
+    const PER_MINUTE: u32 = 5;
+
+    pub(crate) fn new(trust_ip_header: bool) -> Self {
+        Self(RateLimiter::new(Some(Self::PER_MINUTE), trust_ip_header))
+    }
+}
+
+/// Request guard for the admin login, which fails with 429 Too Many Requests if the client has made too many login attempts.
+pub(crate) struct LoginRateLimited;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LoginRateLimited {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, ()> {
+        check(req, req.rocket().state::<LoginRateLimiter>().map(|LoginRateLimiter(limiter)| limiter)).map(|()| Self)
+    }
+}
+
+/// Submissions are always rate limited, independently of the configured lookup rate limit, since each one is stored until it's reviewed.
+pub(crate) struct SubmissionRateLimiter(RateLimiter);
+
+impl SubmissionRateLimiter {
+    const PER_MINUTE: u32 = 2;
+
+    pub(crate) fn new(trust_ip_header: bool) -> Self {
+        Self(RateLimiter::new(Some(Self::PER_MINUTE), trust_ip_header))
+    }
+}
+
+/// Request guard for molecule submissions, which fails with 429 Too Many Requests if the client has made too many submissions.
+pub(crate) struct SubmissionRateLimited;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SubmissionRateLimited {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, ()> {
+        check(req, req.rocket().state::<SubmissionRateLimiter>().map(|SubmissionRateLimiter(limiter)| limiter)).map(|()| Self)
+    }
+}
+
+/// Like [`json::Json`], but limited to the given data limit instead of the `json` one, so each kind of request body can have a limit suited to it.
+async fn from_json<'r, T: DeserializeOwned>(req: &'r Request<'_>, data: Data<'r>, limit: ByteUnit) -> data::Outcome<'r, T, json::Error<'r>> {
+    let string = match data.open(limit).into_string().await {
+        Ok(string) if string.is_complete() => string.into_inner(),
+        Ok(_) => return Outcome::Error((Status::PayloadTooLarge, json::Error::Io(io::Error::new(io::ErrorKind::UnexpectedEof, "data limit exceeded")))),
+        Err(e) => return Outcome::Error((Status::BadRequest, json::Error::Io(e))),
+    };
+    let string = request::local_cache!(req, string);
+    match serde_json::from_str(string) {
+        Ok(value) => Outcome::Success(value),
+        Err(e) if e.classify() == serde_json::error::Category::Data => Outcome::Error((Status::UnprocessableEntity, json::Error::Parse(string, e))),
+        Err(e) => Outcome::Error((Status::BadRequest, json::Error::Parse(string, e))),
+    }
+}
+
+/// A JSON request body limited to the [`LOOKUP_LIMIT_NAME`] data limit, so lookups can be restricted to small bodies.
+pub(crate) struct LookupJson<T>(pub(crate) T);
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned> FromData<'r> for LookupJson<T> {
+    type Error = json::Error<'r>;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let limit = req.limits().get(LOOKUP_LIMIT_NAME).unwrap_or_else(default_lookup_limit);
+        from_json(req, data, limit).await.map(Self)
+    }
+}
+
+/// A JSON request body limited to the [`DIFF_LIMIT_NAME`] data limit, which is large enough for catalogue exports.
+pub(crate) struct DiffJson<T>(pub(crate) T);
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned> FromData<'r> for DiffJson<T> {
+    type Error = json::Error<'r>;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let limit = req.limits().get(DIFF_LIMIT_NAME).unwrap_or_else(default_diff_limit);
+        from_json(req, data, limit).await.map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        std::net::Ipv4Addr,
+        super::*,
+    };
+
+    #[test]
+    fn buckets_are_bounded() {
+        let limiter = RateLimiter::new(Some(1), false);
+        let client = |idx: u32| IpAddr::V4(Ipv4Addr::from(idx));
+        for idx in 0..=Buckets::MAX_CLIENTS as u32 {
+            assert_eq!(limiter.take(client(idx)), Ok(()));
+        }
+        let buckets = limiter.limit.as_ref().expect("rate limit is enabled").buckets.lock().expect("rate limiter poisoned");
+        assert_eq!(buckets.by_client.len(), Buckets::MAX_CLIENTS);
+        assert_eq!(buckets.by_update.len(), Buckets::MAX_CLIENTS);
+        // the least recently seen client was evicted
+        assert!(!buckets.by_client.contains_key(&client(0)));
+        drop(buckets);
+        assert!(limiter.take(client(1)).is_err());
+        assert_eq!(limiter.take(client(0)), Ok(()));
+    }
+}
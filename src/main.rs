@@ -1,47 +1,153 @@
 use {
     std::{
         borrow::Cow,
-        cmp::Ordering::{
-            self,
-            *,
+        cmp::{
+            Ordering::{
+                self,
+                *,
+            },
+            Reverse,
         },
         collections::{
             HashMap,
             HashSet,
         },
+        fmt,
+        io,
+        net::IpAddr,
+        path::PathBuf,
+        sync::OnceLock,
+    },
+    chrono::Utc,
+    clap::Parser as _,
+    enum_iterator::{
+        Sequence,
+        all,
     },
-    enum_iterator::all,
     itertools::Itertools as _,
     omsim_rs::data::*,
     rocket::{
-        http::Status,
-        response::content::{
-            RawCss,
-            RawHtml,
-            RawJavaScript,
+        Build,
+        Orbit,
+        Request,
+        Rocket,
+        State,
+        fairing::AdHoc,
+        figment::{
+            Figment,
+            Profile,
+            providers::{
+                Env,
+                Format as _,
+                Toml,
+            },
+        },
+        form::Form,
+        fs::FileServer,
+        http::{
+            ContentType,
+            Cookie,
+            CookieJar,
+            Header,
+            SameSite,
+            Status,
+        },
+        outcome::Outcome,
+        request::{
+            self,
+            FromRequest,
+        },
+        response::{
+            Debug,
+            Redirect,
+            content::{
+                RawCss,
+                RawHtml,
+                RawJavaScript,
+            },
+        },
+        serde::json::{
+            self,
+            Json,
         },
-        serde::json::Json,
         uri,
     },
     rocket_util::{
         Doctype,
         html,
     },
+    schemars::{
+        JsonSchema,
+        gen::SchemaGenerator,
+        schema::{
+            InstanceType,
+            Schema,
+            SchemaObject,
+        },
+    },
     serde::{
         Deserialize,
         Serialize,
     },
     crate::{
+        caching::{
+            Cache,
+            Cached,
+        },
+        glyphs::ATOMS,
+        known::KnownMolecules,
+        limits::{
+            DiffJson,
+            LoginRateLimited,
+            LookupJson,
+            MoleculeLimits,
+            RateLimited,
+            SubmissionRateLimited,
+        },
+        puzzles::Source,
+        query::{
+            AtomClass,
+            AtomPattern,
+            Query,
+        },
+        submissions::{
+            Review,
+            Submissions,
+        },
+        unknown_log::UnknownLog,
         unparse::Unparse,
-        util::IteratorExt as _,
+        util::{
+            IteratorExt as _,
+            constant_time_eq,
+            fnv1a,
+        },
     },
 };
 
+mod caching;
+mod catalogue;
+mod changelog;
+mod diff;
+mod export;
+mod glyphs;
+mod input;
+mod known;
+mod limits;
+mod metrics;
 mod molecules;
+mod openapi;
+mod puzzle_file;
+mod puzzles;
+mod query;
+mod ratios;
+mod similarity;
+mod stats;
+mod submissions;
+mod unknown_log;
 mod unparse;
 mod util;
 
-#[derive(Serialize)]
+#[derive(Clone, PartialEq, Eq, JsonSchema, Serialize, clap::ValueEnum, rocket::FromFormField)]
 enum InOut {
     Reagent,
     Product,
@@ -51,16 +157,23 @@ enum InOut {
 trait MoleculeExt {
     fn position_normalized(&self) -> Self;
     fn normalized(&self) -> Self;
+    /// An identifier for this molecule which stays the same across catalogue changes. Must be called on a normalized molecule.
+    fn id(&self) -> String;
+    /// A compact text form of this molecule using the builder state's keys and values, e.g. `0,0=salt;1,0=fire;0,0:1,0=n`.
+    fn notation(&self) -> String;
     fn mirrored(&self) -> Self;
+    /// The normalized skeleton of this molecule, i.e. the same bonds and geometry but with every atom replaced by salt.
+    fn shape(&self) -> Self;
+    /// A plain text description of this molecule's atoms and bonds, for assistive technology.
+    fn description(&self) -> String;
+    /// A static drawing of this molecule, shown instead of [`MoleculeExt::draw`]'s canvas if JavaScript is disabled and served as an image.
+    fn svg(&self) -> RawHtml<String>;
     fn draw(&self, id: &str) -> RawHtml<String>;
 }
 
 impl MoleculeExt for Molecule {
     fn position_normalized(&self) -> Self {
-        let offset = HexIndex {
-            q: self.atoms.keys().map(|&HexIndex { q, .. }| q).min().unwrap_or_default(),
-            r: self.atoms.keys().map(|&HexIndex { r, .. }| r).min().unwrap_or_default(),
-        };
+        let offset = query::position_offset(self.atoms.keys());
         let mut normalized = self.mapped_positions(|pos| pos - offset);
         normalized.bonds = normalized.bonds.into_iter().map(|Bond { start, end, ty }| Bond {
             start: if (start.q, start.r) <= (end.q, end.r) { start } else { end },
@@ -139,11 +252,111 @@ impl MoleculeExt for Molecule {
         }
     }
 
+    fn id(&self) -> String {
+        format!("{:016x}", fnv1a(format!("{:?}", Unparse(self)).as_bytes()))
+    }
+
+    fn notation(&self) -> String {
+        self.atoms.iter()
+            .sorted_by_key(|(pos, _)| (pos.q, pos.r))
+            .map(|(pos, &atom)| format!("{},{}={}", pos.q, pos.r, format_atom(atom)))
+            .chain(self.bonds.iter().sorted_by_key(|bond| (bond.start.q, bond.start.r, bond.end.q, bond.end.r)).map(|bond| format!("{},{}:{},{}={}", bond.start.q, bond.start.r, bond.end.q, bond.end.r, export::bond_type_code(bond.ty))))
+            .join(";")
+    }
+
     fn mirrored(&self) -> Self {
         self.mapped_positions(|pos| HexIndex { q: -pos.s(), r: -pos.r, }).position_normalized()
     }
 
+    fn shape(&self) -> Self {
+        Self {
+            atoms: self.atoms.iter().map(|(&pos, &atom)| (pos, if atom == Atom::Repeat { Atom::Repeat } else { Atom::Salt })).collect(),
+            bonds: self.bonds.clone(),
+        }.normalized()
+    }
+
+    fn description(&self) -> String {
+        let atoms = self.atoms.iter()
+            .sorted_by_key(|(pos, _)| (pos.q, pos.r))
+            .map(|(pos, &atom)| format!("{} at {},{}", format_atom(atom), pos.q, pos.r))
+            .join(", ");
+        let bonds = self.bonds.iter()
+            .sorted_by_key(|bond| (bond.start.q, bond.start.r, bond.end.q, bond.end.r))
+            .map(|bond| {
+                let ty = match bond.ty {
+                    BondType::Normal => Cow::Borrowed("normal bond"),
+                    BondType::Triplex { red, black, yellow } => Cow::Owned(format!("triplex bond ({})", [(red, "red"), (black, "black"), (yellow, "yellow")].into_iter().filter_map(|(present, color)| present.then_some(color)).join(", "))),
+                };
+                format!("{ty} between {},{} and {},{}", bond.start.q, bond.start.r, bond.end.q, bond.end.r)
+            })
+            .join(", ");
+        match (self.atoms.len(), self.bonds.len()) {
+            (1, 0) => format!("1 atom: {atoms}."),
+            (num_atoms, 0) => format!("{num_atoms} atoms: {atoms}. No bonds."),
+            (num_atoms, 1) => format!("{num_atoms} atoms: {atoms}. 1 bond: {bonds}."),
+            (num_atoms, num_bonds) => format!("{num_atoms} atoms: {atoms}. {num_bonds} bonds: {bonds}."),
+        }
+    }
+
+    fn svg(&self) -> RawHtml<String> {
+        const RADIUS: i32 = 16;
+
+        fn atom_color(atom: Atom) -> &'static str {
+            match atom {
+                Atom::Salt => "#ddd",
+                Atom::Air => "#9cf",
+                Atom::Earth => "#7b5",
+                Atom::Fire => "#e63",
+                Atom::Water => "#39e",
+                Atom::Quicksilver => "#bbc",
+                Atom::Gold => "#eb3",
+                Atom::Silver => "#ccd",
+                Atom::Copper => "#c74",
+                Atom::Iron => "#966",
+                Atom::Tin => "#998",
+                Atom::Lead => "#667",
+                Atom::Vitae => "#f9b",
+                Atom::Mors => "#547",
+                Atom::Repeat => "#fff",
+                Atom::Quintessence => "#c7e",
+            }
+        }
+
+        let Self { atoms, bonds } = self.mirrored();
+        let min_x = atoms.keys().map(|&HexIndex { q, r }| 2 * q + r).min().unwrap_or_default();
+        let center = |HexIndex { q, r }: HexIndex| ((2 * q + r - min_x) * 20 + RADIUS + 4, r * 35 + RADIUS + 4);
+        let width = atoms.keys().map(|&pos| center(pos).0).max().unwrap_or_default() + RADIUS + 4;
+        let height = atoms.keys().map(|&pos| center(pos).1).max().unwrap_or_default() + RADIUS + 4;
+        let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" role=\"img\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\"><title>{}</title><rect width=\"100%\" height=\"100%\" fill=\"#223\"/>", xml_escape(&self.description()));
+        for Bond { start, end, ty } in bonds {
+            let (x1, y1) = center(start);
+            let (x2, y2) = center(end);
+            match ty {
+                BondType::Normal => svg.push_str(&format!("<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"#ccc\" stroke-width=\"6\"/>")),
+                BondType::Triplex { red, black, yellow } => {
+                    // offset the colored lines perpendicular to the bond so they're drawn side by side
+                    let (dx, dy) = (f64::from(x2 - x1), f64::from(y2 - y1));
+                    let len = dx.hypot(dy);
+                    for (offset, present, color) in [(-4.0, red, "#c33"), (0.0, black, "#111"), (4.0, yellow, "#dc3")] {
+                        if present {
+                            let (ox, oy) = (-dy / len * offset, dx / len * offset);
+                            svg.push_str(&format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{color}\" stroke-width=\"3\"/>", f64::from(x1) + ox, f64::from(y1) + oy, f64::from(x2) + ox, f64::from(y2) + oy));
+                        }
+                    }
+                }
+            }
+        }
+        for (pos, atom) in atoms.into_iter().sorted_by_key(|(pos, _)| (pos.r, pos.q)) {
+            let (x, y) = center(pos);
+            svg.push_str(&format!("<circle cx=\"{x}\" cy=\"{y}\" r=\"{RADIUS}\" fill=\"{}\"><title>{}</title></circle>", atom_color(atom), format_atom(atom)));
+        }
+        svg.push_str("</svg>");
+        RawHtml(svg)
+    }
+
     fn draw(&self, id: &str) -> RawHtml<String> {
+        let svg = self.svg();
+        let description = serde_json::to_string(&self.description()).expect("failed to serialize molecule description").replace("</", "<\\/");
         let Self { atoms, bonds } = self.mirrored();
         let min_x = atoms.keys().map(|&HexIndex { q, r }| 2 * q + r).min().unwrap_or_default();
         let width = atoms.keys().map(|&HexIndex { q, r }| 2 * q + r + 2).max().unwrap_or_default() - min_x;
@@ -152,9 +365,13 @@ impl MoleculeExt for Molecule {
         let height = (71 * height + 20) * 3 / 4;
         html! {
             canvas(id = id);
+            // only parsed if JavaScript is disabled, so pages with many molecules don't include every drawing twice
+            noscript : svg;
             script {
                 : RawHtml(format!("
                     const productCanvas{id} = document.getElementById({id:?});
+                    productCanvas{id}.setAttribute('role', 'img');
+                    productCanvas{id}.setAttribute('aria-label', {description});
                     productCanvas{id}.width = {width} * window.devicePixelRatio;
                     productCanvas{id}.style.width = '{width}px';
                     productCanvas{id}.height = {height} * window.devicePixelRatio;
@@ -195,24 +412,30 @@ impl MoleculeExt for Molecule {
     }
 }
 
-fn parse_atom(s: &str) -> Option<Atom> {
+fn parse_atom(s: &str) -> Option<AtomPattern> {
     match s {
-        "Salt" | "salt" => Some(Atom::Salt),
-        "Air" | "air" => Some(Atom::Air),
-        "Earth" | "earth" => Some(Atom::Earth),
-        "Fire" | "fire" => Some(Atom::Fire),
-        "Water" | "water" => Some(Atom::Water),
-        "Quicksilver" | "quicksilver" => Some(Atom::Quicksilver),
-        "Gold" | "gold" => Some(Atom::Gold),
-        "Silver" | "silver" => Some(Atom::Silver),
-        "Copper" | "copper" => Some(Atom::Copper),
-        "Iron" | "iron" => Some(Atom::Iron),
-        "Tin" | "tin" => Some(Atom::Tin),
-        "Lead" | "lead" => Some(Atom::Lead),
-        "Vitae" | "vitae" => Some(Atom::Vitae),
-        "Mors" | "mors" => Some(Atom::Mors),
-        "Repeat" | "repeat" => Some(Atom::Repeat),
-        "Quintessence" | "quintessence" => Some(Atom::Quintessence),
+        "Salt" | "salt" => Some(AtomPattern::Exact(Atom::Salt)),
+        "Air" | "air" => Some(AtomPattern::Exact(Atom::Air)),
+        "Earth" | "earth" => Some(AtomPattern::Exact(Atom::Earth)),
+        "Fire" | "fire" => Some(AtomPattern::Exact(Atom::Fire)),
+        "Water" | "water" => Some(AtomPattern::Exact(Atom::Water)),
+        "Quicksilver" | "quicksilver" => Some(AtomPattern::Exact(Atom::Quicksilver)),
+        "Gold" | "gold" => Some(AtomPattern::Exact(Atom::Gold)),
+        "Silver" | "silver" => Some(AtomPattern::Exact(Atom::Silver)),
+        "Copper" | "copper" => Some(AtomPattern::Exact(Atom::Copper)),
+        "Iron" | "iron" => Some(AtomPattern::Exact(Atom::Iron)),
+        "Tin" | "tin" => Some(AtomPattern::Exact(Atom::Tin)),
+        "Lead" | "lead" => Some(AtomPattern::Exact(Atom::Lead)),
+        "Vitae" | "vitae" => Some(AtomPattern::Exact(Atom::Vitae)),
+        "Mors" | "mors" => Some(AtomPattern::Exact(Atom::Mors)),
+        "Repeat" | "repeat" => Some(AtomPattern::Exact(Atom::Repeat)),
+        "Quintessence" | "quintessence" => Some(AtomPattern::Exact(Atom::Quintessence)),
+        // wildcards use the same names as the molecule builder's palette
+        "cardinal" => Some(AtomPattern::Class(AtomClass::Cardinal)),
+        "metal" => Some(AtomPattern::Class(AtomClass::Metal)),
+        "salt-quicksilver" => Some(AtomPattern::Class(AtomClass::SaltOrQuicksilver)),
+        "vitae-mors" => Some(AtomPattern::Class(AtomClass::VitaeOrMors)),
+        "any" => Some(AtomPattern::Class(AtomClass::Any)),
         _ => None,
     }
 }
@@ -238,50 +461,207 @@ fn format_atom(atom: Atom) -> &'static str {
     }
 }
 
-#[rocket::get("/")]
-fn index() -> RawHtml<String> {
+fn format_pattern(pattern: AtomPattern) -> &'static str {
+    match pattern {
+        AtomPattern::Exact(atom) => format_atom(atom),
+        AtomPattern::Class(AtomClass::Cardinal) => "cardinal",
+        AtomPattern::Class(AtomClass::Metal) => "metal",
+        AtomPattern::Class(AtomClass::SaltOrQuicksilver) => "salt-quicksilver",
+        AtomPattern::Class(AtomClass::VitaeOrMors) => "vitae-mors",
+        AtomPattern::Class(AtomClass::Any) => "any",
+    }
+}
+
+static BASE_PATH: OnceLock<String> = OnceLock::new();
+
+/// The prefix under which all routes are mounted, without a trailing slash. Empty if the database is served from the root.
+fn base_path() -> &'static str {
+    BASE_PATH.get().map_or("", String::as_str)
+}
+
+/// Prefixes an absolute path with the [`base_path`].
+fn url(path: impl fmt::Display) -> String {
+    format!("{}{path}", base_path())
+}
+
+const COMMON_CSS: &str = include_str!("../assets/static/common.css");
+const COMMON_JS: &str = include_str!("../assets/static/common.js");
+const TRANSMOGRIFICATION_JS: &str = include_str!("../assets/static/transmogrification.js");
+
+/// A hash of the compiled-in copy of the given static asset.
+fn asset_version(file: &str) -> u64 {
+    fnv1a(match file {
+        "common.css" => COMMON_CSS,
+        "common.js" => COMMON_JS,
+        "transmogrification.js" => TRANSMOGRIFICATION_JS,
+        _ => unreachable!("unknown static asset: {file}"),
+    }.as_bytes())
+}
+
+/// The URL of the given static asset, fingerprinted with [`asset_version`] so it can be cached indefinitely.
+fn static_url(file: &str) -> String {
+    url(format!("/static/{file}?v={:016x}", asset_version(file)))
+}
+
+fn base_path_script() -> RawHtml<String> {
+    RawHtml(format!("const basePath = {};", serde_json::to_string(base_path()).expect("failed to serialize base path")))
+}
+
+/// Preloads the molecule builder with the given state. `</` is escaped so the state can't end the script element.
+fn initial_state_script(state: &HashMap<String, String>) -> RawHtml<String> {
+    RawHtml(format!("const initialState = {};", serde_json::to_string(state).expect("failed to serialize initial state").replace("</", "<\\/")))
+}
+
+/// The molecule builder's grid is a hexagon with this radius around `0,0`, see `visit` in transmogrification.js.
+const BUILDER_RADIUS: i32 = 4;
+
+/// The translation which centers the given positions in the molecule builder, or `None` if they don't fit on its grid.
+fn builder_offset<'a>(positions: impl IntoIterator<Item = &'a HexIndex> + Clone) -> Option<HexIndex> {
+    let center = |coords: Vec<i32>| (coords.iter().min().unwrap_or(&0) + coords.iter().max().unwrap_or(&0)) / 2;
+    let offset = HexIndex {
+        q: center(positions.clone().into_iter().map(|pos| pos.q).collect()),
+        r: center(positions.clone().into_iter().map(|pos| pos.r).collect()),
+    };
+    positions.into_iter().all(|&pos| {
+        let HexIndex { q, r } = pos - offset;
+        q.abs() <= BUILDER_RADIUS && r.abs() <= BUILDER_RADIUS && (q + r).abs() <= BUILDER_RADIUS
+    }).then_some(offset)
+}
+
+/// The text notation of the given molecule, moved so it's centered in the molecule builder. `None` if it doesn't fit on the builder's grid.
+fn builder_notation(molecule: &Molecule) -> Option<String> {
+    let offset = builder_offset(molecule.atoms.keys())?;
+    Some(molecule.mapped_positions(|pos| pos - offset).notation())
+}
+
+fn page(title: &str, content: RawHtml<String>) -> RawHtml<String> {
     html! {
         : Doctype;
         html {
             head {
                 meta(charset = "utf-8");
-                title : "Opus Magnum Molecule Database";
+                title : title;
                 meta(name = "viewport", content = "width=device-width, initial-scale=1, shrink-to-fit=no");
-                link(rel = "stylesheet", href = "/static/common.css");
-                script(src = "/static/common.js");
-                script(defer, src = "/static/transmogrification.js");
+                link(rel = "stylesheet", href = static_url("common.css"));
+                script : base_path_script();
+                script(src = static_url("common.js"));
             }
-            body {
-                main(style = "flex-direction: column;") {
-                    div {
-                        h2 : "ENTER MOLECULE TO LOOK UP";
-                        canvas(id = "current");
-                        p(id = "result", style = "display: none;");
-                        p(id = "error");
-                    }
-                    ul(id = "default") {
-                        li {
-                            a(href = uri!(molecules_list).to_string()) : "List of all molecules";
+            body : content;
+        }
+    }
+}
+
+/// With a `molecule` parameter in the text notation (see [`MoleculeExt::notation`]), the builder starts out with that molecule. Invalid molecules are ignored.
+#[rocket::get("/?<molecule>&<source>")]
+fn index(cache: Cache, submissions: &State<Submissions>, molecule: Option<&str>, source: Option<Source>) -> Cached<RawHtml<String>> {
+    cache.respond(|| {
+        let initial_state = molecule
+            .and_then(|molecule| JsState::from_notation(molecule).ok())
+            .and_then(JsState::into_builder_state)
+            .unwrap_or_default();
+        html! {
+            : Doctype;
+            html {
+                head {
+                    meta(charset = "utf-8");
+                    title : "Opus Magnum Molecule Database";
+                    meta(name = "viewport", content = "width=device-width, initial-scale=1, shrink-to-fit=no");
+                    link(rel = "stylesheet", href = static_url("common.css"));
+                    script : base_path_script();
+                    script : initial_state_script(&initial_state);
+                    script(src = static_url("common.js"));
+                    script(defer, src = static_url("transmogrification.js"));
+                }
+                body {
+                    main(style = "flex-direction: column;") {
+                        div {
+                            h2 : "ENTER MOLECULE TO LOOK UP";
+                            canvas(id = "current") {
+                                p {
+                                    : "The molecule builder needs JavaScript. ";
+                                    a(href = url(uri!(lookup(molecule, source)))) : "Look up molecules by their text notation instead.";
+                                }
+                            }
+                            p {
+                                : "Search in ";
+                                select(id = "source") {
+                                    : select_option("", "all puzzles", source.is_none());
+                                    @for iter_source in all::<Source>() {
+                                        : select_option(iter_source.as_str(), &format!("puzzles from {}", iter_source.description()), source == Some(iter_source));
+                                    }
+                                }
+                            }
+                            p(id = "result", style = "display: none;");
+                            p(id = "share", style = "display: none;") {
+                                button(id = "copy-link") : "Copy link to this molecule";
+                            }
+                            p(id = "error");
+                        }
+                        @if submissions.is_enabled() {
+                            form(id = "submission", method = "post", action = url(uri!(submit_molecule)), style = "display: none;") {
+                                h2 : "Submit a name for this molecule";
+                                input(type = "hidden", id = "submission-notation", name = "notation");
+                                input(type = "text", name = "puzzle", placeholder = "puzzle", maxlength = SubmissionForm::MAX_NAME_LEN.to_string(), required);
+                                select(name = "role") {
+                                    option(value = "reagent") : "reagent";
+                                    option(value = "product") : "product";
+                                    option(value = "both") : "both";
+                                }
+                                input(type = "text", name = "name", placeholder = "molecule name", maxlength = SubmissionForm::MAX_NAME_LEN.to_string(), required);
+                                input(type = "url", name = "source", placeholder = "source link, e.g. a screenshot", maxlength = SubmissionForm::MAX_SOURCE_LEN.to_string(), required);
+                                input(type = "submit", value = "Submit for review");
+                            }
+                        }
+                        ul(id = "default") {
+                            li {
+                                a(href = url(uri!(molecules_list(_, _, _, _, _, _, _)))) : "List of all molecules";
+                            }
+                            li {
+                                a(href = url(uri!(lookup(_, _)))) : "Look up a molecule by text notation";
+                            }
+                            li {
+                                a(href = url(uri!(shapes))) : "Molecules with the same shape";
+                            }
+                            li {
+                                a(href = url(uri!(puzzles_list(_)))) : "List of all puzzles";
+                            }
+                            li {
+                                a(href = url(uri!(recent))) : "Recently added molecules";
+                            }
+                            li {
+                                a(href = url(uri!(stats_page))) : "Statistics";
+                            }
+                            li {
+                                : "Download the catalogue as ";
+                                a(href = url(uri!(export_json))) : "JSON";
+                                : ", ";
+                                @for table in all::<export::CsvTable>() {
+                                    a(href = url(uri!(export_csv(table.file_name())))) : table.file_name();
+                                    : ", ";
+                                }
+                                : "or ";
+                                a(href = url(uri!(export_sqlite))) : "SQLite";
+                            }
                         }
                     }
-                }
-                canvas(id = "next", style = "display: none;");
-                footer {
-                    p {
-                        : "hosted by ";
-                        a(href = "https://fenhl.net/") : "Fenhl";
-                        : " • ";
-                        a(href = "https://fenhl.net/disc") : "disclaimer";
-                        : " • ";
-                        a(href = "https://github.com/fenhl/molecule-db") : "source code";
+                    canvas(id = "next", style = "display: none;");
+                    footer {
+                        p {
+                            : "hosted by ";
+                            a(href = "https://fenhl.net/") : "Fenhl";
+                            : " • ";
+                            a(href = "https://fenhl.net/disc") : "disclaimer";
+                            : " • ";
+                            a(href = "https://github.com/fenhl/molecule-db") : "source code";
+                        }
                     }
                 }
             }
         }
-    }
+    })
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct JsState {
     #[allow(unused)] selected_atom: Option<String>,
@@ -290,101 +670,1544 @@ struct JsState {
     rest: HashMap<String, String>,
 }
 
-#[derive(Serialize)]
+/// Parses a molecule in the text notation produced by [`MoleculeExt::notation`]. Returns `None` if it's invalid or contains wildcards.
+fn molecule_from_notation(notation: &str) -> Option<Molecule> {
+    JsState::from_notation(notation).ok()?.into_query().ok()?.to_molecule()
+}
+
+impl JsState {
+    /// Parses the text notation produced by [`MoleculeExt::notation`].
+    fn from_notation(notation: &str) -> Result<Self, Status> {
+        Ok(Self {
+            selected_atom: None,
+            selected_bond: None,
+            rest: notation.split(';').filter(|entry| !entry.is_empty()).map(|entry| entry.split_once('=').map(|(key, value)| (key.to_owned(), value.to_owned())).ok_or(Status::BadRequest)).try_collect()?,
+        })
+    }
+
+    fn into_query(self) -> Result<Query, Status> {
+        let mut query = Query { atoms: HashMap::default(), bonds: HashSet::default() };
+        for (key, value) in self.rest {
+            if let Some((start, end)) = key.split_once(':') {
+                let (q1, r1) = start.split_once(',').ok_or(Status::BadRequest)?;
+                let (q2, r2) = end.split_once(',').ok_or(Status::BadRequest)?;
+                query.bonds.insert(Bond {
+                    start: HexIndex { q: q1.parse().map_err(|_| Status::BadRequest)?, r: r1.parse().map_err(|_| Status::BadRequest)? },
+                    end: HexIndex { q: q2.parse().map_err(|_| Status::BadRequest)?, r: r2.parse().map_err(|_| Status::BadRequest)? },
+                    ty: if value == "n" {
+                        BondType::Normal
+                    } else {
+                        BondType::Triplex { red: value.contains('r'), black: value.contains('k'), yellow: value.contains('y') }
+                    },
+                });
+            } else {
+                let (q, r) = key.split_once(',').ok_or(Status::BadRequest)?;
+                query.atoms.insert(HexIndex { q: q.parse().map_err(|_| Status::BadRequest)?, r: r.parse().map_err(|_| Status::BadRequest)? }, parse_atom(&value).ok_or(Status::BadRequest)?);
+            }
+        }
+        Ok(query)
+    }
+
+    /// The builder state for this molecule, moved so it's centered in the molecule builder like [`builder_notation`]. `None` if it's invalid or doesn't fit on the builder's grid.
+    fn into_builder_state(self) -> Option<HashMap<String, String>> {
+        let Query { atoms, bonds } = self.into_query().ok()?;
+        let offset = builder_offset(atoms.keys())?;
+        Some(
+            atoms.into_iter().map(|(pos, pattern)| {
+                let HexIndex { q, r } = pos - offset;
+                (format!("{q},{r}"), format_pattern(pattern).to_owned())
+            })
+            .chain(bonds.into_iter().map(|Bond { start, end, ty }| {
+                let (start, end) = (start - offset, end - offset);
+                // orient bonds like `canonicalizeBond` in transmogrification.js so the builder finds them
+                let (start, end) = if matches!((end.q - start.q, end.r - start.r), (1, 0) | (-1, 1) | (0, 1)) { (start, end) } else { (end, start) };
+                (format!("{},{}:{},{}", start.q, start.r, end.q, end.r), export::bond_type_code(ty))
+            }))
+            .collect()
+        )
+    }
+}
+
+type Appearances = Vec<(String, InOut, String)>;
+
+fn owned_appearances(appearances: Vec<(&'static str, InOut, &'static str)>) -> Appearances {
+    appearances.into_iter().map(|(puzzle_name, inout, name)| (puzzle_name.to_owned(), inout, name.to_owned())).collect()
+}
+
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct MoleculeResponse {
-    appearances: Vec<(String, InOut, String)>,
-    rust_code: String,
-}
-
-#[rocket::post("/api/v1/molecule-from-state", format = "json", data = "<state>")]
-fn molecule_from_state(state: Json<JsState>) -> Result<Json<MoleculeResponse>, Status> {
-    let Json(JsState { rest, .. }) = state;
-    let mut molecule = Molecule { atoms: HashMap::default(), bonds: HashSet::default() };
-    for (key, value) in rest {
-        if let Some((start, end)) = key.split_once(':') {
-            let (q1, r1) = start.split_once(',').ok_or(Status::BadRequest)?;
-            let (q2, r2) = end.split_once(',').ok_or(Status::BadRequest)?;
-            molecule.bonds.insert(Bond {
-                start: HexIndex { q: q1.parse().map_err(|_| Status::BadRequest)?, r: r1.parse().map_err(|_| Status::BadRequest)? },
-                end: HexIndex { q: q2.parse().map_err(|_| Status::BadRequest)?, r: r2.parse().map_err(|_| Status::BadRequest)? },
-                ty: if value == "n" {
-                    BondType::Normal
+    appearances: Appearances,
+    /// Appearances of other known molecules which have the same bonds and geometry but different atoms.
+    same_shape: Vec<Appearances>,
+    /// If the query contains wildcard atoms, the appearances of each known molecule matching it.
+    matches: Vec<Appearances>,
+    /// Appearances of other known molecules which contain the query as a part, i.e. have all of its atoms and bonds and possibly more.
+    containing: Vec<Appearances>,
+    /// If the molecule is unknown, the known molecules which are the fewest edits away from it.
+    suggestions: Vec<Suggestion>,
+    /// `None` if the query contains wildcard atoms.
+    rust_code: Option<String>,
+    /// The normalized molecule in the notation accepted by the submission form, `None` if the query contains wildcard atoms.
+    notation: Option<String>,
+    /// The normalized molecule, `None` if the query contains wildcard atoms.
+    canonical: Option<CanonicalMolecule>,
+    catalogue_version: u32,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct CanonicalMolecule {
+    id: String,
+    atoms: Vec<export::AtomJson>,
+    bonds: Vec<export::BondJson>,
+    /// The number of 60° counterclockwise rotations around `0,0` which, followed by `translation`, map the drawn molecule onto this one.
+    rotation: u8,
+    translation: [i32; 2],
+    /// `None` if the molecule isn't in the catalogue.
+    page_url: Option<String>,
+    /// An SVG drawing of the molecule, `None` if the molecule isn't in the catalogue.
+    image_url: Option<String>,
+}
+
+/// How the given molecule is mapped onto the given normalized form of it: a number of 60° counterclockwise rotations around `0,0`, followed by a translation.
+fn normalization(molecule: &Molecule, normalized: &Molecule) -> Option<(u8, HexIndex)> {
+    let mut rotated = molecule.clone();
+    for rotation in 0..6 {
+        if rotated.position_normalized() == *normalized {
+            return Some((rotation, HexIndex::default() - query::position_offset(rotated.atoms.keys())))
+        }
+        rotated = rotated.mapped_positions(query::rotate_ccw);
+    }
+    None
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct Suggestion {
+    distance: usize,
+    appearances: Appearances,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(untagged)]
+enum BatchResult {
+    Found(MoleculeResponse),
+    Error {
+        error: String,
+    },
+}
+
+/// Looks up several molecules at once. Each molecule is either a string in text notation, an object with `atoms` and `bonds` in the format of the `canonical` field of lookup responses, or a builder state.
+/// The results are in the same order as the molecules, with an object containing only an `error` message for each molecule which couldn't be parsed or is too large.
+/// Responds with 413 Payload Too Large if there are more molecules than the configured maximum.
+#[rocket::post("/api/v1/batch-lookup?<source>", format = "json", data = "<molecules>")]
+fn batch_lookup(_rate_limit: RateLimited, known: &State<KnownMolecules>, unknown_log: &State<UnknownLog>, metrics: &State<metrics::Metrics>, molecule_limits: &State<MoleculeLimits>, source: Option<Source>, molecules: LookupJson<Vec<serde_json::Value>>) -> Result<Json<Vec<BatchResult>>, (Status, Json<ApiError>)> {
+    let LookupJson(molecules) = molecules;
+    molecule_limits.check_batch(molecules.len()).map_err(|e| ApiError::new(Status::PayloadTooLarge, e))?;
+    let mut unknown = unknown_log::Batch::default();
+    let results = molecules.into_iter().map(|molecule| match input::query_from_json(molecule) {
+        Ok(query) => match molecule_limits.check(&query) {
+            Ok(()) => BatchResult::Found(lookup_query(known, &mut unknown, metrics, source, &query)),
+            Err(e) => BatchResult::Error { error: e.to_string() },
+        },
+        Err(e) => BatchResult::Error { error: e.to_string() },
+    }).collect();
+    unknown_log.record(unknown);
+    Ok(Json(results))
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ApiError {
+    error: String,
+}
+
+impl ApiError {
+    fn new(status: Status, error: impl ToString) -> (Status, Json<Self>) {
+        (status, Json(Self { error: error.to_string() }))
+    }
+}
+
+fn json_error(e: json::Error<'_>) -> (Status, Json<ApiError>) {
+    match e {
+        json::Error::Io(e) if e.kind() == io::ErrorKind::UnexpectedEof => ApiError::new(Status::PayloadTooLarge, "the request body is too large"),
+        json::Error::Io(e) => ApiError::new(Status::BadRequest, e),
+        json::Error::Parse(_, e) => ApiError::new(Status::UnprocessableEntity, e),
+    }
+}
+
+#[rocket::catch(413)]
+fn payload_too_large() -> Json<ApiError> {
+    Json(ApiError { error: "the request is too large".to_owned() })
+}
+
+#[derive(rocket::Responder)]
+#[response(status = 429)]
+struct TooManyRequests {
+    inner: Json<ApiError>,
+    retry_after: Header<'static>,
+}
+
+#[rocket::catch(429)]
+fn too_many_requests(req: &Request<'_>) -> TooManyRequests {
+    // only set by the rate limiter, which is the only source of 429 responses
+    let limits::RetryAfter(seconds) = req.local_cache(|| limits::RetryAfter(60));
+    TooManyRequests {
+        inner: Json(ApiError { error: format!("too many requests, try again in {seconds} seconds") }),
+        retry_after: Header::new("Retry-After", seconds.to_string()),
+    }
+}
+
+/// Like [`molecule_from_state`], but takes the molecule as lists of atoms and bonds (see [`input::StructuredMolecule`]) and responds with a JSON error message if it's invalid.
+#[rocket::post("/api/v2/lookup?<source>", format = "json", data = "<molecule>")]
+fn lookup_v2(_rate_limit: RateLimited, known: &State<KnownMolecules>, unknown_log: &State<UnknownLog>, metrics: &State<metrics::Metrics>, molecule_limits: &State<MoleculeLimits>, source: Option<Source>, molecule: Result<LookupJson<input::StructuredMolecule>, json::Error<'_>>) -> Result<Json<MoleculeResponse>, (Status, Json<ApiError>)> {
+    let LookupJson(molecule) = molecule.map_err(json_error)?;
+    let query = molecule.into_query().map_err(|e| ApiError::new(Status::UnprocessableEntity, e))?;
+    molecule_limits.check(&query).map_err(|e| ApiError::new(Status::PayloadTooLarge, e))?;
+    let mut unknown = unknown_log::Batch::default();
+    let response = lookup_query(known, &mut unknown, metrics, source, &query);
+    unknown_log.record(unknown);
+    Ok(Json(response))
+}
+
+#[rocket::get("/api/openapi.json")]
+fn openapi_json(cache: Cache, rocket: &Rocket<Orbit>) -> Cached<Json<serde_json::Value>> {
+    cache.respond(|| Json(openapi::spec(rocket.routes())))
+}
+
+/// Checks whether any of the given appearances is in a puzzle from the given source, or if there is no source filter.
+fn has_source(appearances: &[(&'static str, InOut, &'static str)], source: Option<Source>) -> bool {
+    source.map_or(true, |source| appearances.iter().any(|(puzzle, _, _)| Source::of(puzzle) == Some(source)))
+}
+
+/// Links for filtering the list page at the given path by puzzle source.
+fn source_links(current: Option<Source>, path: impl fmt::Display) -> RawHtml<String> {
+    html! {
+        p {
+            : "Puzzles from: ";
+            @if current.is_none() {
+                : "anywhere";
+            } else {
+                a(href = url(&path)) : "anywhere";
+            }
+            @for source in all::<Source>() {
+                : " • ";
+                @if current == Some(source) {
+                    : source.description();
                 } else {
-                    BondType::Triplex { red: value.contains('r'), black: value.contains('k'), yellow: value.contains('y') }
-                },
-            });
-        } else {
-            let (q, r) = key.split_once(',').ok_or(Status::BadRequest)?;
-            molecule.atoms.insert(HexIndex { q: q.parse().map_err(|_| Status::BadRequest)?, r: r.parse().map_err(|_| Status::BadRequest)? }, parse_atom(&value).ok_or(Status::BadRequest)?);
+                    a(href = url(format!("{path}?source={}", source.as_str()))) : source.description();
+                }
+            }
         }
     }
-    let molecule = molecule.normalized();
+}
+
+/// With a `source` parameter, only molecules appearing in puzzles from that source are considered as matches, suggestions, and molecules with the same shape.
+#[rocket::post("/api/v1/molecule-from-state?<source>", format = "json", data = "<state>")]
+fn molecule_from_state(_rate_limit: RateLimited, known: &State<KnownMolecules>, unknown_log: &State<UnknownLog>, metrics: &State<metrics::Metrics>, molecule_limits: &State<MoleculeLimits>, source: Option<Source>, state: LookupJson<JsState>) -> Result<Json<MoleculeResponse>, (Status, Json<ApiError>)> {
+    let LookupJson(state) = state;
+    let query = state.into_query().map_err(|status| ApiError::new(status, "invalid builder state"))?;
+    molecule_limits.check(&query).map_err(|e| ApiError::new(Status::PayloadTooLarge, e))?;
+    let mut unknown = unknown_log::Batch::default();
+    let response = lookup_query(known, &mut unknown, metrics, source, &query);
+    unknown_log.record(unknown);
+    Ok(Json(response))
+}
+
+/// Unknown molecules are added to `unknown` for the caller to record in the [`UnknownLog`].
+fn lookup_query(known: &KnownMolecules, unknown: &mut unknown_log::Batch, metrics: &metrics::Metrics, source: Option<Source>, query: &Query) -> MoleculeResponse {
+    let shape = query.skeleton().normalized();
+    let drawn = query.to_molecule();
+    let molecule = drawn.as_ref().map(|molecule| molecule.normalized());
     let mut response = MoleculeResponse {
         appearances: Vec::default(),
-        rust_code: format!("{:?}", Unparse(&molecule)),
+        same_shape: Vec::default(),
+        matches: Vec::default(),
+        containing: Vec::default(),
+        suggestions: Vec::default(),
+        rust_code: molecule.as_ref().map(|molecule| format!("{:?}", Unparse(molecule))),
+        notation: molecule.as_ref().map(|molecule| molecule.notation()),
+        canonical: None,
+        catalogue_version: changelog::CATALOGUE_VERSION,
     };
-    for (iter_molecule, appearances) in molecules::molecules() {
-        if iter_molecule == molecule {
-            response.appearances = appearances.into_iter().map(|(puzzle_name, inout, name)| (puzzle_name.to_owned(), inout, name.to_owned())).collect();
-            break
+    let exact = molecule.as_ref().and_then(|molecule| known.get(molecule));
+    if let Some(entry) = exact {
+        response.appearances = owned_appearances(entry.appearances.clone());
+    }
+    let mut suggestions = Vec::default();
+    for entry in &known.entries {
+        if !has_source(&entry.appearances, source) {
+            continue
+        }
+        if let Some(ref molecule) = molecule {
+            if exact.is_some_and(|exact| exact.id == entry.id) {
+                continue
+            }
+            if entry.shape == shape {
+                response.same_shape.push(owned_appearances(entry.appearances.clone()));
+            }
+            if query.is_part_of(&entry.molecule) {
+                response.containing.push(owned_appearances(entry.appearances.clone()));
+            }
+            // suggestions are only shown for unknown molecules
+            if exact.is_none() {
+                if let Some(distance) = similarity::edit_distance(molecule, &entry.molecule, similarity::MAX_SUGGESTION_DISTANCE) {
+                    suggestions.push(Suggestion { distance, appearances: owned_appearances(entry.appearances.clone()) });
+                }
+            }
+        } else if query.matches(&entry.molecule) {
+            response.matches.push(owned_appearances(entry.appearances.clone()));
+        } else if query.is_part_of(&entry.molecule) {
+            response.containing.push(owned_appearances(entry.appearances.clone()));
         }
     }
-    Ok(Json(response))
+    if let (Some(drawn), Some(ref molecule)) = (drawn, molecule) {
+        metrics.record_lookup(!response.appearances.is_empty());
+        if response.appearances.is_empty() {
+            suggestions.sort_by_key(|suggestion| suggestion.distance);
+            suggestions.truncate(5);
+            response.suggestions = suggestions;
+            unknown.add(molecule);
+        }
+        if let Some((rotation, translation)) = normalization(&drawn, molecule) {
+            let id = molecule.id();
+            let known = !response.appearances.is_empty();
+            response.canonical = Some(CanonicalMolecule {
+                atoms: export::atoms_json(molecule),
+                bonds: export::bonds_json(molecule),
+                translation: [translation.q, translation.r],
+                page_url: known.then(|| url(uri!(molecule_page(&*id)))),
+                image_url: known.then(|| url(uri!(molecule_image(&*id)))),
+                id, rotation,
+            });
+        }
+    }
+    response
 }
 
-#[rocket::get("/molecules")]
-fn molecules_list() -> RawHtml<String> {
+/// Lists molecules with links to their pages, for the server-rendered lookup page.
+fn appearance_list(molecules: &[Appearances]) -> RawHtml<String> {
     html! {
-        : Doctype;
-        html {
-            head {
-                meta(charset = "utf-8");
-                title : "Opus Magnum Molecule Database";
-                meta(name = "viewport", content = "width=device-width, initial-scale=1, shrink-to-fit=no");
-                link(rel = "stylesheet", href = "/static/common.css");
-                script(src = "/static/common.js");
+        ul {
+            @for appearances in molecules {
+                @let name = appearances.iter().map(|(_, _, name)| name).sorted_unstable().dedup().join("/");
+                li {
+                    a(href = url(uri!(molecule_page(appearances[0].2.as_str())))) : name;
+                    : format!(" ({})", appearances.iter().map(|(puzzle, _, _)| puzzle).dedup().join(", "));
+                }
+            }
+        }
+    }
+}
+
+fn exact_lookup_results(molecule: &Molecule, response: MoleculeResponse) -> RawHtml<String> {
+    html! {
+        : molecule.draw("molecule");
+        @if response.appearances.is_empty() {
+            p : "This molecule isn't in the database.";
+            @if !response.suggestions.is_empty() {
+                h2 : "Similar molecules";
+                : appearance_list(&response.suggestions.into_iter().map(|suggestion| suggestion.appearances).collect_vec());
             }
-            body {
+        } else {
+            p {
+                : "This is ";
+                a(href = url(uri!(molecule_page(response.appearances[0].2.as_str())))) : response.appearances.iter().map(|(_, _, name)| name).sorted_unstable().dedup().join("/");
+                : format!(", which appears in {}.", response.appearances.iter().map(|(puzzle, _, _)| puzzle).dedup().join(", "));
+            }
+        }
+        @if !response.same_shape.is_empty() {
+            h2 : "Molecules with the same shape";
+            : appearance_list(&response.same_shape);
+        }
+        @if !response.containing.is_empty() {
+            h2 : "Molecules containing this one";
+            : appearance_list(&response.containing);
+        }
+    }
+}
+
+fn pattern_lookup_results(response: MoleculeResponse) -> RawHtml<String> {
+    html! {
+        @if response.matches.is_empty() {
+            p : "No molecules match this pattern.";
+        } else {
+            h2 : "Matching molecules";
+            : appearance_list(&response.matches);
+        }
+        @if !response.containing.is_empty() {
+            h2 : "Molecules containing this pattern";
+            : appearance_list(&response.containing);
+        }
+    }
+}
+
+/// A lookup form which works without JavaScript, using the same text notation as [`MoleculeExt::notation`].
+#[rocket::get("/lookup?<notation>&<source>")]
+fn lookup(_rate_limit: RateLimited, known: &State<KnownMolecules>, unknown_log: &State<UnknownLog>, metrics: &State<metrics::Metrics>, molecule_limits: &State<MoleculeLimits>, notation: Option<&str>, source: Option<Source>) -> RawHtml<String> {
+    let notation = notation.map(str::trim).filter(|notation| !notation.is_empty());
+    let results = match notation.map(|notation| JsState::from_notation(notation).and_then(JsState::into_query)) {
+        None => RawHtml(String::default()),
+        Some(Err(_)) => html! {
+            p : "This isn't a valid molecule.";
+        },
+        Some(Ok(query)) => if let Err(e) = molecule_limits.check(&query) {
+            html! {
+                p : format!("This molecule is too large to look up: {e}.");
+            }
+        } else {
+            let mut unknown = unknown_log::Batch::default();
+            let response = lookup_query(known, &mut unknown, metrics, source, &query);
+            unknown_log.record(unknown);
+            if let Some(molecule) = query.to_molecule() {
+                exact_lookup_results(&molecule.normalized(), response)
+            } else {
+                pattern_lookup_results(response)
+            }
+        }
+    };
+    page("Look up a molecule — Opus Magnum Molecule Database", html! {
+        h1 : "Look up a molecule";
+        form(method = "get", action = url(uri!(lookup(_, _)))) {
+            p {
+                label {
+                    : "Molecule: ";
+                    input(type = "text", name = "notation", size = "60", value = notation.unwrap_or_default(), required);
+                }
+            }
+            p {
+                label {
+                    : "Search in ";
+                    select(name = "source") {
+                        : select_option("", "all puzzles", source.is_none());
+                        @for iter_source in all::<Source>() {
+                            : select_option(iter_source.as_str(), &format!("puzzles from {}", iter_source.description()), source == Some(iter_source));
+                        }
+                    }
+                }
+                : " ";
+                input(type = "submit", value = "Look up");
+            }
+        }
+        p {
+            : "Write each atom as its position and type, e.g. ";
+            code : "0,0=salt";
+            : ", and each bond as the positions of its atoms and ";
+            code : "n";
+            : " for a normal bond or any of ";
+            code : "r";
+            : ", ";
+            code : "k";
+            : ", and ";
+            code : "y";
+            : " for the red, black, and yellow parts of a triplex bond, e.g. ";
+            code : "0,0:1,0=n";
+            : ". Separate them with semicolons. Positions are axial hex coordinates. Instead of an atom type, you can write ";
+            code : "cardinal";
+            : ", ";
+            code : "metal";
+            : ", ";
+            code : "salt-quicksilver";
+            : ", ";
+            code : "vitae-mors";
+            : ", or ";
+            code : "any";
+            : " to find all molecules matching a pattern. Molecules which contain the molecule or pattern as a part are listed as well.";
+        }
+        : results;
+    })
+}
+
+fn display_name(appearances: &[(&'static str, InOut, &'static str)]) -> String {
+    appearances.iter().map(|(_, _, name)| name).sorted_unstable().dedup().join("/")
+}
+
+#[rocket::get("/api/v1/shapes")]
+fn shapes_json(cache: Cache, known: &State<KnownMolecules>) -> Cached<Json<Vec<Vec<Appearances>>>> {
+    cache.respond(|| Json(known.shape_groups().map(|group| group.map(|entry| owned_appearances(entry.appearances.clone())).collect()).collect()))
+}
+
+#[rocket::get("/shapes")]
+fn shapes(cache: Cache, known: &State<KnownMolecules>) -> Cached<RawHtml<String>> {
+    cache.respond(|| {
+        page("Opus Magnum Molecule Database", html! {
+            @for (group_idx, group) in known.shape_groups().enumerate() {
                 main {
-                    @for (idx, (molecule, appearances)) in molecules::molecules().into_iter().sorted_unstable_by_key(|(_, appearances)| appearances.iter().map(|(_, _, name)| name).min().map(|name| name.to_owned())).enumerate() {
+                    @for (idx, entry) in group.enumerate() {
                         div {
-                            h2 : appearances.iter().map(|(_, _, name)| name).sorted_unstable().dedup().join("/");
-                            : molecule.draw(&format!("product{idx}"));
+                            h2 {
+                                a(href = url(uri!(molecule_page(&*entry.id)))) : display_name(&entry.appearances);
+                            }
+                            : entry.molecule.draw(&format!("shape{group_idx}x{idx}"));
                         }
                     }
                 }
             }
+        })
+    })
+}
+
+/// How many molecules are shown on each page of the molecule list.
+const PAGE_SIZE: usize = 30;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Sequence, rocket::FromFormField)]
+enum SortBy {
+    #[default]
+    Name,
+    Size,
+    Atoms,
+    Appearances,
+}
+
+impl SortBy {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Size => "size",
+            Self::Atoms => "atoms",
+            Self::Appearances => "appearances",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Size => "size (largest first)",
+            Self::Atoms => "number of atoms (most first)",
+            Self::Appearances => "number of appearances (most first)",
         }
     }
 }
 
+/// Described in terms of [`SortBy::as_str`], which is also what form parsing accepts, so the OpenAPI description can't disagree with the query parameter.
+impl JsonSchema for SortBy {
+    fn schema_name() -> String {
+        "SortBy".to_owned()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(all::<Self>().map(|sort| sort.as_str().into()).collect()),
+            ..SchemaObject::default()
+        }.into()
+    }
+}
+
+/// The largest hex distance between any two atoms of a molecule.
+fn extent(molecule: &Molecule) -> i32 {
+    molecule.atoms.keys()
+        .tuple_combinations()
+        .map(|(pos1, pos2)| {
+            let (dq, dr) = (pos1.q - pos2.q, pos1.r - pos2.r);
+            (dq.abs() + dr.abs() + (dq + dr).abs()) / 2
+        })
+        .max()
+        .unwrap_or_default()
+}
+
+/// Checks whether an appearance with role `actual` matches a role filter. Molecules which are both reagent and product match all filters.
+fn role_matches(filter: &InOut, actual: &InOut) -> bool {
+    actual == filter || *actual == InOut::Both
+}
+
+/// The state of the molecule list page, as given in the query string.
+struct MoleculeListQuery<'a> {
+    /// Only set if it's a valid atom type or class, so it's safe to include in URLs unescaped.
+    atom: Option<&'a str>,
+    min_atoms: Option<usize>,
+    max_atoms: Option<usize>,
+    source: Option<Source>,
+    role: Option<InOut>,
+    sort: SortBy,
+}
+
+impl MoleculeListQuery<'_> {
+    fn matches(&self, molecule: &Molecule, appearances: &[(&'static str, InOut, &'static str)]) -> bool {
+        self.atom.and_then(parse_atom).map_or(true, |pattern| molecule.atoms.values().any(|&atom| pattern.matches(atom)))
+        && self.min_atoms.map_or(true, |min_atoms| molecule.atoms.len() >= min_atoms)
+        && self.max_atoms.map_or(true, |max_atoms| molecule.atoms.len() <= max_atoms)
+        && appearances.iter().any(|(puzzle, inout, _)| self.source.map_or(true, |source| Source::of(puzzle) == Some(source)) && self.role.as_ref().map_or(true, |role| role_matches(role, inout)))
+    }
+
+    /// The URL of the given page of the molecule list with this state.
+    fn url(&self, page: usize) -> String {
+        let mut params = Vec::default();
+        if let Some(atom) = self.atom { params.push(format!("atom={atom}")) }
+        if let Some(min_atoms) = self.min_atoms { params.push(format!("min_atoms={min_atoms}")) }
+        if let Some(max_atoms) = self.max_atoms { params.push(format!("max_atoms={max_atoms}")) }
+        if let Some(source) = self.source { params.push(format!("source={}", source.as_str())) }
+        if let Some(ref role) = self.role { params.push(format!("role={}", export::role(role))) }
+        if self.sort != SortBy::Name { params.push(format!("sort={}", self.sort.as_str())) }
+        if page > 1 { params.push(format!("page={page}")) }
+        if params.is_empty() {
+            url(uri!(molecules_list(_, _, _, _, _, _, _)))
+        } else {
+            url(format!("{}?{}", uri!(molecules_list(_, _, _, _, _, _, _)), params.join("&")))
+        }
+    }
+}
+
+fn select_option(value: &str, label: &str, selected: bool) -> RawHtml<String> {
+    html! {
+        @if selected {
+            option(value = value, selected) : label;
+        } else {
+            option(value = value) : label;
+        }
+    }
+}
+
+/// Invalid or empty parameters are ignored, so submitting the filter form with some fields left blank works as expected.
+#[rocket::get("/molecules?<atom>&<min_atoms>&<max_atoms>&<source>&<role>&<sort>&<page>")]
+fn molecules_list(cache: Cache, atom: Option<&str>, min_atoms: Option<usize>, max_atoms: Option<usize>, source: Option<Source>, role: Option<InOut>, sort: Option<SortBy>, page: Option<usize>) -> Cached<RawHtml<String>> {
+    cache.respond(|| {
+        let query = MoleculeListQuery {
+            atom: atom.filter(|atom| parse_atom(atom).is_some()),
+            sort: sort.unwrap_or_default(),
+            min_atoms, max_atoms, source, role,
+        };
+        molecules_list_page(&query, page.unwrap_or(1))
+    })
+}
+
+fn molecules_list_page(query: &MoleculeListQuery<'_>, page_number: usize) -> RawHtml<String> {
+    let mut molecules = molecules::molecules().into_iter()
+        .filter(|(molecule, appearances)| query.matches(molecule, appearances))
+        .map(|(molecule, appearances)| {
+            let name = appearances.iter().map(|(_, _, name)| *name).min().unwrap_or_default();
+            (name, molecule, appearances)
+        })
+        .collect_vec();
+    match query.sort {
+        SortBy::Name => molecules.sort_unstable_by_key(|(name, _, _)| *name),
+        SortBy::Size => molecules.sort_unstable_by_key(|(name, molecule, _)| (Reverse(extent(molecule)), *name)),
+        SortBy::Atoms => molecules.sort_unstable_by_key(|(name, molecule, _)| (Reverse(molecule.atoms.len()), *name)),
+        SortBy::Appearances => molecules.sort_unstable_by_key(|(name, _, appearances)| (Reverse(appearances.len()), *name)),
+    }
+    let num_pages = molecules.len().div_ceil(PAGE_SIZE).max(1);
+    let page_number = page_number.clamp(1, num_pages);
+    let total = molecules.len();
+    let atom_options = ATOMS.into_iter().map(format_atom).chain(["cardinal", "metal"]).collect_vec();
+    page("Opus Magnum Molecule Database", html! {
+        form(method = "get", action = url(uri!(molecules_list(_, _, _, _, _, _, _)))) {
+            p {
+                : "Containing ";
+                select(name = "atom") {
+                    : select_option("", "any atom", query.atom.is_none());
+                    @for atom in atom_options {
+                        : select_option(atom, atom, query.atom.is_some_and(|query_atom| parse_atom(query_atom) == parse_atom(atom)));
+                    }
+                }
+                : " with ";
+                input(type = "number", name = "min_atoms", min = "1", placeholder = "min", value = query.min_atoms.map(|n| n.to_string()).unwrap_or_default());
+                : " to ";
+                input(type = "number", name = "max_atoms", min = "1", placeholder = "max", value = query.max_atoms.map(|n| n.to_string()).unwrap_or_default());
+                : " atoms, appearing as ";
+                select(name = "role") {
+                    : select_option("", "reagent or product", query.role.is_none());
+                    : select_option("reagent", "reagent", query.role == Some(InOut::Reagent));
+                    : select_option("product", "product", query.role == Some(InOut::Product));
+                    : select_option("both", "both", query.role == Some(InOut::Both));
+                }
+                : " in puzzles from ";
+                select(name = "source") {
+                    : select_option("", "anywhere", query.source.is_none());
+                    @for source in all::<Source>() {
+                        : select_option(source.as_str(), source.description(), query.source == Some(source));
+                    }
+                }
+                : ", sorted by ";
+                select(name = "sort") {
+                    @for sort in all::<SortBy>() {
+                        : select_option(sort.as_str(), sort.description(), query.sort == sort);
+                    }
+                }
+                : " ";
+                input(type = "submit", value = "Filter");
+            }
+        }
+        p {
+            @if page_number > 1 {
+                a(href = query.url(page_number - 1)) : "← previous";
+                : " • ";
+            }
+            : format!("{total} molecules, page {page_number} of {num_pages}");
+            @if page_number < num_pages {
+                : " • ";
+                a(href = query.url(page_number + 1)) : "next →";
+            }
+        }
+        main {
+            @for (idx, (_, molecule, appearances)) in molecules.into_iter().skip((page_number - 1) * PAGE_SIZE).take(PAGE_SIZE).enumerate() {
+                div {
+                    h2 {
+                        a(href = url(uri!(molecule_page(molecule.id())))) : display_name(&appearances);
+                    }
+                    : molecule.draw(&format!("product{idx}"));
+                }
+            }
+        }
+    })
+}
+
+#[rocket::get("/molecule/<id>")]
+fn molecule_page(cache: Cache, known: &State<KnownMolecules>, id: &str) -> Cached<Option<RawHtml<String>>> {
+    cache.respond(|| {
+        let entry = match &*known.find(id) {
+            [] => return None,
+            &[entry] => entry,
+            entries => return Some(page(&format!("{id} — Opus Magnum Molecule Database"), html! {
+                h1 : format!("Molecules named {id}");
+                main {
+                    @for (idx, entry) in entries.iter().enumerate() {
+                        div {
+                            h2 {
+                                a(href = url(uri!(molecule_page(&*entry.id)))) : display_name(&entry.appearances);
+                            }
+                            : entry.molecule.draw(&format!("product{idx}"));
+                        }
+                    }
+                }
+            })),
+        };
+        let (molecule, appearances) = (&entry.molecule, &entry.appearances);
+        let name = display_name(appearances);
+        let added = if let Some(date) = changelog::added(appearances, &changelog::changelog()) {
+            format!("Added to the database on {}.", date.format("%Y-%m-%d"))
+        } else {
+            "Added to the database before its changelog was started.".to_owned()
+        };
+        Some(page(&format!("{name} — Opus Magnum Molecule Database"), html! {
+            h1 : &name;
+            : molecule.draw("molecule");
+            p {
+                : "Notation: ";
+                code : molecule.notation();
+                @for notation in builder_notation(molecule) {
+                    : " (";
+                    a(href = url(uri!(index(Some(notation), _)))) : "open in builder";
+                    : ")";
+                }
+            }
+            p : added;
+            ul {
+                @for (puzzle_name, inout, _) in appearances {
+                    li {
+                        a(href = url(uri!(puzzle_page(*puzzle_name)))) : *puzzle_name;
+                        : match inout {
+                            InOut::Reagent => " (reagent)",
+                            InOut::Product => " (product)",
+                            InOut::Both => " (reagent and product)",
+                        };
+                    }
+                }
+            }
+            @if !molecule.atoms.values().any(|&atom| atom == Atom::Repeat) {
+                p {
+                    a(href = url(uri!(molecule_sandbox_puzzle(&*entry.id))), download = format!("{name}.puzzle")) : "Download as sandbox puzzle";
+                }
+            }
+        }))
+    })
+}
+
+#[rocket::get("/molecule/<id>/image.svg")]
+fn molecule_image(cache: Cache, known: &State<KnownMolecules>, id: &str) -> Cached<Option<(ContentType, String)>> {
+    cache.respond(|| {
+        let entry = known.find_unique(id)?;
+        Some((ContentType::SVG, entry.molecule.svg().0))
+    })
+}
+
+#[rocket::get("/molecule/<id>/sandbox.puzzle")]
+fn molecule_sandbox_puzzle(cache: Cache, known: &State<KnownMolecules>, id: &str) -> Cached<Option<(ContentType, Vec<u8>)>> {
+    cache.respond(|| {
+        let entry = known.find_unique(id)?;
+        Some((ContentType::Binary, puzzle_file::sandbox_puzzle(&display_name(&entry.appearances), &entry.molecule)?))
+    })
+}
+
+#[rocket::post("/api/v1/sandbox-puzzle-from-state", format = "json", data = "<state>")]
+fn sandbox_puzzle_from_state(_rate_limit: RateLimited, known: &State<KnownMolecules>, molecule_limits: &State<MoleculeLimits>, state: LookupJson<JsState>) -> Result<(ContentType, Vec<u8>), Status> {
+    let LookupJson(state) = state;
+    let query = state.into_query()?;
+    molecule_limits.check(&query).map_err(|_| Status::PayloadTooLarge)?;
+    let molecule = query.to_molecule().ok_or(Status::BadRequest)?.normalized();
+    let name = known.get(&molecule).map_or_else(|| "Custom Molecule".to_owned(), |entry| display_name(&entry.appearances));
+    Ok((ContentType::Binary, puzzle_file::sandbox_puzzle(&name, &molecule).ok_or(Status::UnprocessableEntity)?))
+}
+
+#[rocket::get("/puzzles?<source>")]
+fn puzzles_list(cache: Cache, puzzles: &State<puzzles::Puzzles>, source: Option<Source>) -> Cached<RawHtml<String>> {
+    cache.respond(|| {
+        page("Puzzles — Opus Magnum Molecule Database", html! {
+            : source_links(source, uri!(puzzles_list(_)));
+            ul {
+                @for puzzle in puzzles.iter().map(|analyzed| &analyzed.puzzle).filter(|puzzle| source.map_or(true, |source| puzzle.source == Some(source))) {
+                    li {
+                        a(href = url(uri!(puzzle_page(puzzle.name)))) : puzzle.name;
+                    }
+                }
+            }
+        })
+    })
+}
+
+fn glyph_list(glyphs: &[glyphs::Glyph]) -> String {
+    if glyphs.is_empty() {
+        "none".to_owned()
+    } else {
+        glyphs.iter().map(|glyph| glyph.name()).join(", ")
+    }
+}
+
+#[rocket::get("/puzzle/<name>")]
+fn puzzle_page(cache: Cache, puzzles: &State<puzzles::Puzzles>, name: &str) -> Cached<Option<RawHtml<String>>> {
+    cache.respond(|| {
+        let analyzed = puzzles.get(name)?;
+        let puzzle = &analyzed.puzzle;
+        let puzzles::Analysis { glyphs: analysis, ratio } = analyzed.analysis();
+        let ratio_line = if let Some(ratio) = ratio {
+            format!("{} → {} of each product", ratio.reagents.iter().map(|(name, count)| format!("{count}× {name}")).join(" + "), ratio.products)
+        } else {
+            "No ratio in which every reagent atom ends up in a product or is disposed of was found.".to_owned()
+        };
+        let glyph_lines = if let Some(ref sufficient) = analysis.sufficient {
+            vec![
+                format!("Necessary: {}", glyph_list(&analysis.necessary)),
+                format!("Sufficient: {}", glyph_list(sufficient)),
+            ]
+        } else {
+            vec!["The products can't be made from the reagents using transmutation glyphs.".to_owned()]
+        };
+        Some(page(&format!("{} — Opus Magnum Molecule Database", puzzle.name), html! {
+            h1 : puzzle.name;
+            @for source in puzzle.source {
+                p : format!("from {}", source.description());
+            }
+            h2 : "Reagents";
+            main {
+                @for (idx, (molecule, name)) in puzzle.reagents.iter().enumerate() {
+                    div {
+                        h2 : *name;
+                        : molecule.draw(&format!("reagent{idx}"));
+                    }
+                }
+            }
+            h2 : "Products";
+            main {
+                @for (idx, (molecule, name)) in puzzle.products.iter().enumerate() {
+                    div {
+                        h2 : *name;
+                        : molecule.draw(&format!("product{idx}"));
+                    }
+                }
+            }
+            h2 : "Atom balance (one of each product minus one of each reagent)";
+            ul {
+                @for (atom, difference) in &analysis.balance {
+                    li : format!("{atom}: {difference:+}");
+                }
+            }
+            h2 : "Glyphs";
+            @for line in glyph_lines {
+                p : line;
+            }
+            h2 : "Minimal reagent ratio";
+            p : ratio_line;
+        }))
+    })
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct PuzzleResponse<'a> {
+    name: &'static str,
+    source: Option<Source>,
+    reagents: Vec<&'static str>,
+    products: Vec<&'static str>,
+    glyphs: &'a glyphs::GlyphAnalysis,
+    /// The minimal reagent ratio using the glyphs in `glyphs.sufficient`.
+    ratio: Option<&'a ratios::Ratio>,
+    catalogue_version: u32,
+}
+
+#[rocket::get("/api/v1/puzzle/<name>")]
+fn puzzle_json<'a>(cache: Cache, puzzles: &'a State<puzzles::Puzzles>, name: &str) -> Cached<Option<Json<PuzzleResponse<'a>>>> {
+    cache.respond(|| {
+        let analyzed = puzzles.get(name)?;
+        let puzzle = &analyzed.puzzle;
+        let puzzles::Analysis { glyphs, ratio } = analyzed.analysis();
+        Some(Json(PuzzleResponse {
+            name: puzzle.name,
+            source: puzzle.source,
+            reagents: puzzle.reagents.iter().map(|(_, name)| *name).collect(),
+            products: puzzle.products.iter().map(|(_, name)| *name).collect(),
+            ratio: ratio.as_ref(),
+            glyphs,
+            catalogue_version: changelog::CATALOGUE_VERSION,
+        }))
+    })
+}
+
+/// The admin token, if one is configured. See [`Admin`].
+struct AdminToken(Option<String>);
+
+impl AdminToken {
+    /// The value of the private `admin_session` cookie set by [`admin_login`]. Derived from the token so that changing the token logs out existing sessions.
+    fn session(token: &str) -> String {
+        format!("{:016x}", fnv1a(token.as_bytes()))
+    }
+}
+
+/// Request guard for admin pages, which requires the private `admin_session` cookie set by [`admin_login`] for the configured admin token.
+/// Forwards with a 404 if no admin token is configured.
+struct Admin;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, ()> {
+        match req.rocket().state::<AdminToken>() {
+            Some(AdminToken(Some(token))) => if req.cookies().get_private("admin_session").is_some_and(|cookie| cookie.value() == AdminToken::session(token)) {
+                Outcome::Success(Self)
+            } else {
+                Outcome::Error((Status::Unauthorized, ()))
+            },
+            _ => Outcome::Forward(Status::NotFound),
+        }
+    }
+}
+
+#[derive(rocket::FromForm)]
+struct LoginForm {
+    token: String,
+}
+
+#[rocket::get("/admin/login")]
+fn admin_login_page(admin_token: &State<AdminToken>) -> Option<RawHtml<String>> {
+    admin_token.0.as_ref()?;
+    Some(page("Admin login — Opus Magnum Molecule Database", html! {
+        h1 : "Admin login";
+        form(method = "post", action = url(uri!(admin_login))) {
+            input(type = "password", name = "token", placeholder = "admin token", required);
+            input(type = "submit", value = "Log in");
+        }
+    }))
+}
+
+#[rocket::post("/admin/login", data = "<form>")]
+fn admin_login(_rate_limit: LoginRateLimited, admin_token: &State<AdminToken>, cookies: &CookieJar<'_>, form: Form<LoginForm>) -> Result<Redirect, Status> {
+    let Some(ref token) = admin_token.0 else { return Err(Status::NotFound) };
+    let LoginForm { token: submitted } = form.into_inner();
+    if !constant_time_eq(submitted.as_bytes(), token.as_bytes()) {
+        return Err(Status::Unauthorized)
+    }
+    cookies.add_private(Cookie::build(("admin_session", AdminToken::session(token))).path(url("/admin")).http_only(true).secure(true).same_site(SameSite::Strict));
+    Ok(Redirect::to(url(uri!(admin_submissions))))
+}
+
+#[rocket::get("/admin/unknown-molecules")]
+fn unknown_molecules(_admin: Admin, unknown_log: &State<UnknownLog>) -> Result<Option<RawHtml<String>>, Debug<rusqlite::Error>> {
+    let Some(entries) = unknown_log.most_frequent(100)? else { return Ok(None) };
+    let entries = entries.into_iter().filter_map(|entry| Some((molecule_from_notation(&entry.notation)?, entry))).collect_vec();
+    Ok(Some(page("Unknown molecules — Opus Magnum Molecule Database", html! {
+        h1 : "Most frequently looked up unknown molecules";
+        main {
+            @for (idx, (molecule, entry)) in entries.into_iter().enumerate() {
+                div {
+                    h2 : format!("{} lookups", entry.hits);
+                    p : format!("first seen {}, last seen {}", entry.first_seen.format("%Y-%m-%d %H:%M:%S UTC"), entry.last_seen.format("%Y-%m-%d %H:%M:%S UTC"));
+                    : molecule.draw(&format!("unknown{idx}"));
+                    pre : entry.rust_code;
+                }
+            }
+        }
+    })))
+}
+
+#[derive(rocket::FromForm)]
+struct SubmissionForm {
+    #[field(validate = len(..=SubmissionForm::MAX_NOTATION_LEN))]
+    #[field(validate = with(|notation: &String| molecule_from_notation(notation).is_some(), "not a valid molecule"))]
+    notation: String,
+    #[field(validate = len(1..=SubmissionForm::MAX_NAME_LEN))]
+    puzzle: String,
+    role: InOut,
+    #[field(validate = len(1..=SubmissionForm::MAX_NAME_LEN))]
+    name: String,
+    #[field(validate = len(..=SubmissionForm::MAX_SOURCE_LEN))]
+    #[field(validate = with(|source: &String| is_link(source), "source must be an http or https link"))]
+    source: String,
+}
+
+impl SubmissionForm {
+    /// Enough for the notation of a molecule within the default [`MoleculeLimits`].
+    const MAX_NOTATION_LEN: usize = 16 * 1024;
+    /// The maximum length of puzzle and molecule names, far longer than any in the game.
+    const MAX_NAME_LEN: usize = 100;
+    const MAX_SOURCE_LEN: usize = 2048;
+}
+
+fn is_link(source: &str) -> bool {
+    rocket::http::uri::Absolute::parse(source).is_ok_and(|uri| {
+        let web = uri.scheme().eq_ignore_ascii_case("https") || uri.scheme().eq_ignore_ascii_case("http");
+        web && uri.authority().is_some_and(|authority| !authority.host().is_empty())
+    })
+}
+
+#[rocket::post("/submit", data = "<form>")]
+fn submit_molecule(_rate_limit: RateLimited, _submission_rate_limit: SubmissionRateLimited, submissions: &State<Submissions>, form: Form<SubmissionForm>) -> Result<Option<RawHtml<String>>, Debug<rusqlite::Error>> {
+    let SubmissionForm { notation, puzzle, role, name, source } = form.into_inner();
+    let Some(molecule) = molecule_from_notation(&notation) else { return Ok(None) };
+    if !submissions.submit(&molecule.normalized().notation(), &puzzle, &role, &name, &source)? {
+        return Ok(None)
+    }
+    Ok(Some(page("Submission received — Opus Magnum Molecule Database", html! {
+        h1 : "Thank you!";
+        p : "Your submission will be added to the database once it's been reviewed.";
+        p {
+            a(href = url(uri!(index(_, _)))) : "Look up another molecule";
+        }
+    })))
+}
+
+#[rocket::get("/admin/submissions")]
+fn admin_submissions(_admin: Admin, submissions: &State<Submissions>) -> Result<Option<RawHtml<String>>, Debug<rusqlite::Error>> {
+    let Some(pending) = submissions.with_review(Review::Pending)? else { return Ok(None) };
+    let pending = pending.into_iter().filter_map(|submission| Some((molecule_from_notation(&submission.notation)?, submission))).collect_vec();
+    Ok(Some(page("Submissions — Opus Magnum Molecule Database", html! {
+        h1 : "Pending submissions";
+        p {
+            a(href = url(uri!(approved_submissions))) : "Download approved submissions as catalogue entries";
+        }
+        main {
+            @for (molecule, submission) in pending {
+                div {
+                    h2 : submission.name;
+                    p : format!("{} in {}", export::role(&submission.role), submission.puzzle);
+                    p {
+                        : "source: ";
+                        a(href = submission.source.clone()) : submission.source;
+                    }
+                    p : format!("submitted {}", submission.submitted.format("%Y-%m-%d %H:%M:%S UTC"));
+                    : molecule.draw(&format!("submission{}", submission.id));
+                    form(method = "post", action = url(uri!(approve_submission(submission.id)))) {
+                        input(type = "submit", value = "Approve");
+                    }
+                    form(method = "post", action = url(uri!(reject_submission(submission.id)))) {
+                        input(type = "submit", value = "Reject");
+                    }
+                }
+            }
+        }
+    })))
+}
+
+#[rocket::post("/admin/submissions/<id>/approve")]
+fn approve_submission(_admin: Admin, submissions: &State<Submissions>, id: i64) -> Result<Option<Redirect>, Debug<rusqlite::Error>> {
+    Ok(submissions.review(id, Review::Approved)?.then(|| Redirect::to(url(uri!(admin_submissions)))))
+}
+
+#[rocket::post("/admin/submissions/<id>/reject")]
+fn reject_submission(_admin: Admin, submissions: &State<Submissions>, id: i64) -> Result<Option<Redirect>, Debug<rusqlite::Error>> {
+    Ok(submissions.review(id, Review::Rejected)?.then(|| Redirect::to(url(uri!(admin_submissions)))))
+}
+
+/// All approved submissions, grouped by molecule and formatted as entries for `src/molecules.rs`.
+#[rocket::get("/admin/submissions/approved.rs")]
+fn approved_submissions(_admin: Admin, submissions: &State<Submissions>) -> Result<Option<(ContentType, String)>, Debug<rusqlite::Error>> {
+    let Some(approved) = submissions.with_review(Review::Approved)? else { return Ok(None) };
+    let mut entries = Vec::<(Molecule, Vec<(&str, InOut, &str)>)>::default();
+    for submission in &approved {
+        let Some(molecule) = molecule_from_notation(&submission.notation) else { continue };
+        let appearance = (&*submission.puzzle, submission.role.clone(), &*submission.name);
+        if let Some((_, appearances)) = entries.iter_mut().find(|(iter_molecule, _)| *iter_molecule == molecule) {
+            appearances.push(appearance);
+        } else {
+            entries.push((molecule, vec![appearance]));
+        }
+    }
+    Ok(Some((ContentType::Plain, entries.iter().map(|(molecule, appearances)| catalogue::entry(molecule, appearances)).collect())))
+}
+
+#[rocket::get("/stats")]
+fn stats_page(cache: Cache, stats: &State<stats::Stats>) -> Cached<RawHtml<String>> {
+    cache.respond(|| {
+        page("Statistics — Opus Magnum Molecule Database", html! {
+            h1 : "Statistics";
+            p : format!("{} molecules, {} of them with triplex bonds and {} polymers", stats.molecules, stats.triplex, stats.polymers);
+            h2 : "Appearances by puzzle source";
+            ul {
+                @for (source, count) in &stats.appearances_by_source {
+                    li : format!("{}: {count}", source.description());
+                }
+            }
+            h2 : "Atoms by type";
+            ul {
+                @for (atom, count) in &stats.atom_frequency {
+                    li : format!("{atom}: {count}");
+                }
+            }
+            h2 : "Molecules by number of atoms";
+            ul {
+                @for (size, count) in &stats.size_distribution {
+                    li : format!("{size} {}: {count}", if *size == 1 { "atom" } else { "atoms" });
+                }
+            }
+            h2 : "Most reused molecules";
+            ol {
+                @for molecule in &stats.most_reused {
+                    li {
+                        a(href = url(uri!(molecule_page(&*molecule.id)))) : &*molecule.name;
+                        : format!(" ({} appearances)", molecule.count);
+                    }
+                }
+            }
+            h2 : "Largest molecules";
+            ol {
+                @for molecule in &stats.largest {
+                    li {
+                        a(href = url(uri!(molecule_page(&*molecule.id)))) : &*molecule.name;
+                        : format!(" ({} atoms)", molecule.count);
+                    }
+                }
+            }
+            h2 : "Molecules by symmetry";
+            p : "C means rotational symmetry only, D means also symmetric under reflection. The number is how many rotations map the molecule to itself.";
+            ul {
+                @for (class, count) in &stats.symmetry {
+                    li : format!("{class}: {count}");
+                }
+            }
+            p {
+                a(href = url(uri!(stats_json))) : "JSON";
+            }
+        })
+    })
+}
+
+#[rocket::get("/api/v1/stats")]
+fn stats_json(cache: Cache, stats: &State<stats::Stats>) -> Cached<Json<&stats::Stats>> {
+    cache.respond(|| Json(stats.inner()))
+}
+
+/// Prometheus metrics, see [`metrics::Metrics`].
+#[rocket::get("/metrics")]
+fn metrics_endpoint(metrics: &State<metrics::Metrics>, stats: &State<stats::Stats>) -> (ContentType, String) {
+    (ContentType::new("text", "plain").with_params(("version", "0.0.4")), metrics.render(stats))
+}
+
+/// Responds with 503 Service Unavailable if any of the configured databases can't be queried.
+#[rocket::get("/healthz")]
+fn healthz(unknown_log: &State<UnknownLog>, submissions: &State<Submissions>) -> (Status, &'static str) {
+    match unknown_log.check().and_then(|()| submissions.check()) {
+        Ok(()) => (Status::Ok, "ok"),
+        Err(e) => {
+            rocket::error!("health check failed: {e}");
+            (Status::ServiceUnavailable, "database unavailable")
+        }
+    }
+}
+
+/// The number of entries on the recently added page and in its feed.
+const RECENT_LIMIT: usize = 50;
+
+#[rocket::get("/recent")]
+fn recent(cache: Cache) -> Cached<RawHtml<String>> {
+    cache.respond(|| {
+        page("Recently added — Opus Magnum Molecule Database", html! {
+            h1 : "Recently added molecules";
+            p : changelog::STARTED_NOTE;
+            p {
+                a(href = url(uri!(recent_atom))) : "Atom feed";
+            }
+            main {
+                @for (idx, (date, molecule, appearances)) in changelog::recent(RECENT_LIMIT).into_iter().enumerate() {
+                    div {
+                        h2 {
+                            a(href = url(uri!(molecule_page(molecule.id())))) : display_name(&appearances);
+                        }
+                        p : format!("added {}", date.format("%Y-%m-%d"));
+                        : molecule.draw(&format!("recent{idx}"));
+                    }
+                }
+            }
+        })
+    })
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// The public URL of this site, including the base path, for links in feeds.
+struct PublicUrl(String);
+
+#[rocket::get("/recent.atom")]
+fn recent_atom(cache: Cache, public_url: &State<PublicUrl>) -> Cached<(ContentType, String)> {
+    cache.with_catalogue_date().respond(|| {
+        let PublicUrl(ref public_url) = **public_url;
+        let recent = changelog::recent(RECENT_LIMIT);
+        let updated = recent.first().map(|(date, _, _)| *date).unwrap_or_default();
+        let entries = recent.iter().map(|(date, molecule, appearances)| {
+            let link = format!("{public_url}{}", uri!(molecule_page(molecule.id())));
+            let puzzles = appearances.iter().map(|(puzzle, _, _)| *puzzle).unique().join(", ");
+            format!(
+                "<entry><id>{link}</id><title>{}</title><link href=\"{link}\"/><updated>{}T00:00:00Z</updated><summary>{}</summary></entry>",
+                xml_escape(&display_name(appearances)),
+                date.format("%Y-%m-%d"),
+                xml_escape(&format!("appears in {puzzles}")),
+            )
+        }).collect::<String>();
+        (ContentType::new("application", "atom+xml"), format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\"><id>{public_url}{}</id><title>Opus Magnum Molecule Database: recently added molecules</title><subtitle>{}</subtitle><link rel=\"self\" href=\"{public_url}{}\"/><link href=\"{public_url}{}\"/><updated>{}T00:00:00Z</updated>{entries}</feed>\n",
+            uri!(recent_atom),
+            xml_escape(changelog::STARTED_NOTE),
+            uri!(recent_atom),
+            uri!(recent),
+            updated.format("%Y-%m-%d"),
+        ))
+    })
+}
+
+/// Compares a catalogue JSON export, e.g. one cached by a client, to the current catalogue or to another export.
+#[rocket::post("/api/v1/catalogue-diff", format = "json", data = "<request>")]
+fn catalogue_diff(_rate_limit: RateLimited, request: Result<DiffJson<diff::DiffRequest>, json::Error<'_>>) -> Result<Json<diff::Diff>, (Status, Json<ApiError>)> {
+    let DiffJson(request) = request.map_err(json_error)?;
+    Ok(Json(request.diff()))
+}
+
+#[rocket::get("/export/catalogue.json")]
+fn export_json(cache: Cache) -> Cached<Json<export::CatalogueJson>> {
+    cache.with_catalogue_date().respond(|| Json(export::json()))
+}
+
+#[rocket::get("/export/catalogue.sqlite")]
+fn export_sqlite(cache: Cache, export: &State<export::SqliteExport>) -> Cached<(ContentType, &[u8])> {
+    cache.with_catalogue_date().respond(|| (ContentType::new("application", "vnd.sqlite3"), &export.0))
+}
+
+#[rocket::get("/export/<file>")]
+fn export_csv(cache: Cache, file: &str) -> Cached<Option<(ContentType, String)>> {
+    cache.with_catalogue_date().respond(|| {
+        let table = all::<export::CsvTable>().find(|table| table.file_name() == file)?;
+        Some((ContentType::CSV, export::csv(table)))
+    })
+}
+
 #[rocket::get("/static/common.css")]
-fn common_css() -> RawCss<&'static str> {
-    RawCss(include_str!("../assets/static/common.css"))
+fn common_css(cache: Cache) -> Cached<RawCss<&'static str>> {
+    cache.respond(|| RawCss(COMMON_CSS))
 }
 
 #[rocket::get("/static/common.js")]
-fn common_js() -> RawJavaScript<&'static str> {
-    RawJavaScript(include_str!("../assets/static/common.js"))
+fn common_js(cache: Cache) -> Cached<RawJavaScript<&'static str>> {
+    cache.respond(|| RawJavaScript(COMMON_JS))
 }
 
 #[rocket::get("/static/transmogrification.js")]
-fn transmogrification_js() -> RawJavaScript<&'static str> {
-    RawJavaScript(include_str!("../assets/static/transmogrification.js"))
-}
-
-#[rocket::launch]
-fn rocket() -> _ {
-    rocket::custom(rocket::Config {
-        port: 24821,
-        ..rocket::Config::default()
-    }).mount("/", rocket::routes![
-        index,
-        molecule_from_state,
-        molecules_list,
-        common_css,
-        common_js,
-        transmogrification_js,
-    ])
+fn transmogrification_js(cache: Cache) -> Cached<RawJavaScript<&'static str>> {
+    cache.respond(|| RawJavaScript(TRANSMOGRIFICATION_JS))
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Settings {
+    /// Prefix for all routes, for serving the database from a subdirectory behind a reverse proxy.
+    base_path: String,
+    /// If set, static assets are served from this directory instead of the copies compiled into the binary.
+    static_dir: Option<PathBuf>,
+    /// If set, lookups of unknown molecules are counted in an SQLite database at this path and listed at `/admin/unknown-molecules`.
+    unknown_molecule_log: Option<PathBuf>,
+    /// If set, visitors can submit names for unknown molecules, which are stored in an SQLite database at this path and reviewed at `/admin/submissions`.
+    submission_queue: Option<PathBuf>,
+    /// The token required to log in at `/admin/login`. Admin pages are disabled if this isn't set.
+    admin_token: Option<String>,
+    /// The URL under which this site is publicly reachable, including the base path. Used for links in feeds.
+    public_url: Option<String>,
+    /// If set, each client can make at most this many lookups, catalogue diffs, and submissions per minute. Clients are told apart by IP address.
+    rate_limit: Option<u32>,
+    /// If set, client IP addresses are read from Rocket's `ip_header` (`X-Real-IP` by default) instead of the connection. Only enable this behind a reverse proxy which sets that header.
+    trust_ip_header: bool,
+    /// Lookups of molecules with more atoms than this are rejected. Defaults to [`MoleculeLimits::DEFAULT_MAX_ATOMS`].
+    max_atoms: Option<usize>,
+    /// Lookups of molecules with more bonds than this are rejected. Defaults to [`MoleculeLimits::DEFAULT_MAX_BONDS`].
+    max_bonds: Option<usize>,
+    /// Batch lookups of more molecules than this are rejected. Defaults to [`MoleculeLimits::DEFAULT_MAX_BATCH`].
+    max_batch: Option<usize>,
+}
+
+/// Rocket's configuration sources (`Rocket.toml` and `ROCKET_` environment variables) with our default port, overridden by command-line flags.
+fn figment(args: &ServerArgs) -> Figment {
+    let mut figment = Figment::from(rocket::Config { port: 24821, ..rocket::Config::default() })
+        .merge(Toml::file(Env::var_or("ROCKET_CONFIG", "Rocket.toml")).nested())
+        .merge(Env::prefixed("ROCKET_").ignore(&["PROFILE"]).global())
+        .select(Profile::from_env_or("ROCKET_PROFILE", rocket::Config::DEFAULT_PROFILE));
+    if let Some(address) = args.address {
+        figment = figment.merge(("address", address));
+    }
+    if let Some(port) = args.port {
+        figment = figment.merge(("port", port));
+    }
+    if let Some(ref base_path) = args.base_path {
+        figment = figment.merge(("base_path", base_path));
+    }
+    if let Some(ref static_dir) = args.static_dir {
+        figment = figment.merge(("static_dir", static_dir));
+    }
+    if let Some(ref unknown_molecule_log) = args.unknown_molecule_log {
+        figment = figment.merge(("unknown_molecule_log", unknown_molecule_log));
+    }
+    if let Some(ref submission_queue) = args.submission_queue {
+        figment = figment.merge(("submission_queue", submission_queue));
+    }
+    if let Some(ref admin_token) = args.admin_token {
+        figment = figment.merge(("admin_token", admin_token));
+    }
+    figment
+}
+
+fn rocket(figment: Figment, settings: Settings) -> Result<Rocket<Build>, Error> {
+    let base_path = settings.base_path.trim_end_matches('/');
+    let base_path = if base_path.is_empty() || base_path.starts_with('/') { base_path.to_owned() } else { format!("/{base_path}") };
+    let _ = BASE_PATH.set(base_path.clone());
+    let mount_point = if base_path.is_empty() { "/" } else { base_path.as_str() };
+    let public_url = settings.public_url.map_or_else(|| "https://mol.fenhl.net".to_owned(), |public_url| public_url.trim_end_matches('/').to_owned());
+    let content_version = caching::ContentVersion::compute(&format!("{base_path}\n{public_url}\n{}", settings.submission_queue.is_some()));
+    let rocket = rocket::custom(figment)
+        .attach(AdHoc::on_response("Catalogue version header", |_, response| Box::pin(async move {
+            response.set_raw_header("X-Catalogue-Version", changelog::CATALOGUE_VERSION.to_string());
+        })))
+        .attach(metrics::RequestMetrics)
+        .manage(UnknownLog::open(settings.unknown_molecule_log.as_deref())?)
+        .manage(Submissions::open(settings.submission_queue.as_deref())?)
+        .manage(stats::Stats::compute())
+        .manage(KnownMolecules::compute())
+        .manage(puzzles::Puzzles::compute())
+        .manage(export::SqliteExport::generate()?)
+        .manage(metrics::Metrics::new())
+        .manage(limits::RateLimiter::new(settings.rate_limit, settings.trust_ip_header))
+        .manage(limits::LoginRateLimiter::new(settings.trust_ip_header))
+        .manage(limits::SubmissionRateLimiter::new(settings.trust_ip_header))
+        .manage(MoleculeLimits {
+            max_atoms: settings.max_atoms.unwrap_or(MoleculeLimits::DEFAULT_MAX_ATOMS),
+            max_bonds: settings.max_bonds.unwrap_or(MoleculeLimits::DEFAULT_MAX_BONDS),
+            max_batch: settings.max_batch.unwrap_or(MoleculeLimits::DEFAULT_MAX_BATCH),
+        })
+        .manage(AdminToken(settings.admin_token))
+        .manage(PublicUrl(public_url))
+        .manage(content_version)
+        .mount(mount_point, rocket::routes![
+            index,
+            molecule_from_state,
+            batch_lookup,
+            lookup_v2,
+            openapi_json,
+            lookup,
+            molecules_list,
+            molecule_page,
+            molecule_image,
+            molecule_sandbox_puzzle,
+            sandbox_puzzle_from_state,
+            shapes,
+            shapes_json,
+            puzzles_list,
+            puzzle_page,
+            puzzle_json,
+            export_json,
+            export_sqlite,
+            export_csv,
+            recent,
+            recent_atom,
+            stats_page,
+            stats_json,
+            metrics_endpoint,
+            healthz,
+            catalogue_diff,
+            admin_login_page,
+            admin_login,
+            unknown_molecules,
+            submit_molecule,
+            admin_submissions,
+            approve_submission,
+            reject_submission,
+            approved_submissions,
+        ])
+        .register(mount_point, rocket::catchers![
+            payload_too_large,
+            too_many_requests,
+        ]);
+    Ok(if let Some(static_dir) = settings.static_dir {
+        rocket.mount(format!("{base_path}/static"), FileServer::from(static_dir))
+    } else {
+        rocket.mount(mount_point, rocket::routes![
+            common_css,
+            common_js,
+            transmogrification_js,
+        ])
+    })
+}
+
+#[derive(clap::Parser)]
+#[clap(version)]
+struct Args {
+    #[clap(flatten)]
+    server: ServerArgs,
+    #[clap(subcommand)]
+    subcommand: Option<Subcommand>,
+}
+
+#[derive(clap::Args)]
+struct ServerArgs {
+    /// The IP address to listen on, overriding the Rocket configuration
+    #[clap(long)]
+    address: Option<IpAddr>,
+    /// The port to listen on, overriding the Rocket configuration
+    #[clap(long)]
+    port: Option<u16>,
+    /// Serve all pages under this path prefix, e.g. /molecules
+    #[clap(long)]
+    base_path: Option<String>,
+    /// Serve static assets from this directory instead of the copies compiled into the binary
+    #[clap(long)]
+    static_dir: Option<PathBuf>,
+    /// Count lookups of unknown molecules in an SQLite database at this path
+    #[clap(long)]
+    unknown_molecule_log: Option<PathBuf>,
+    /// Accept submissions of molecule names and store them in an SQLite database at this path
+    #[clap(long)]
+    submission_queue: Option<PathBuf>,
+    /// Enable the admin pages, with this token for logging in
+    #[clap(long)]
+    admin_token: Option<String>,
+}
+
+#[derive(clap::Subcommand)]
+enum Subcommand {
+    /// Write an Opus Magnum sandbox puzzle with a known molecule as the product
+    ExportPuzzle {
+        /// The molecule's ID or one of its names
+        molecule: String,
+        /// Defaults to the molecule's name with a .puzzle extension
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Write the entire catalogue in a format suitable for offline analysis
+    ExportCatalogue {
+        #[clap(value_enum)]
+        format: ExportFormat,
+        /// The file to write, or the directory to write the tables to for CSV
+        output: PathBuf,
+    },
+    /// Add an appearance of a molecule to the catalogue source code, merging it into the molecule's entry if it's already known
+    AddEntry {
+        /// The molecule in the notation returned by the lookup API, e.g. 0,0=salt;1,0=fire;0,0:1,0=n
+        molecule: String,
+        /// The name of the puzzle
+        puzzle: String,
+        #[clap(value_enum)]
+        role: InOut,
+        /// What the puzzle calls the molecule
+        name: String,
+        /// Where the puzzle is from, required if it's not in the catalogue yet unless it's a weekly puzzle, whose name ends in *
+        #[clap(long, value_enum)]
+        source: Option<Source>,
+        #[clap(long, default_value = "src/molecules.rs")]
+        catalogue: PathBuf,
+        /// The catalogue version in this file is incremented, and the new appearance is added to the changelog with today's date
+        #[clap(long, default_value = "src/changelog.rs")]
+        changelog: PathBuf,
+        /// New puzzles are listed with their source in this file
+        #[clap(long, default_value = "src/puzzles.rs")]
+        puzzles: PathBuf,
+    },
+    /// Compare two catalogue JSON exports and print the differences as JSON
+    DiffCatalogue {
+        /// The older export
+        from: PathBuf,
+        /// The newer export, defaults to the catalogue this was built with
+        to: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ExportFormat {
+    Json,
+    Csv,
+    Sqlite,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error(transparent)] Catalogue(#[from] catalogue::Error),
+    #[error(transparent)] Config(#[from] rocket::figment::Error),
+    #[error(transparent)] Export(#[from] export::Error),
+    #[error(transparent)] Io(#[from] io::Error),
+    #[error(transparent)] Json(#[from] serde_json::Error),
+    #[error(transparent)] Rocket(#[from] rocket::Error),
+    #[error(transparent)] Sql(#[from] rusqlite::Error),
+    #[error("no molecule with the ID or name {0:?}")]
+    UnknownMolecule(String),
+    #[error("there are multiple molecules named {0:?}, use one of their IDs instead: {}", .1.join(", "))]
+    AmbiguousMolecule(String, Vec<String>),
+    #[error("polymers and very large molecules can't be exported as puzzle files")]
+    PuzzleFile,
+    #[error("not a valid molecule: {0:?}")]
+    InvalidNotation(String),
+}
+
+#[rocket::main]
+async fn main() -> Result<(), Error> {
+    let args = Args::parse();
+    match args.subcommand {
+        None => {
+            let figment = figment(&args.server);
+            let settings = figment.extract::<Settings>()?;
+            let _ = rocket(figment, settings)?.launch().await?;
+        }
+        Some(Subcommand::ExportPuzzle { molecule, output }) => {
+            let known = KnownMolecules::compute();
+            let entry = match &*known.find(&molecule) {
+                [] => return Err(Error::UnknownMolecule(molecule)),
+                &[entry] => entry,
+                entries => return Err(Error::AmbiguousMolecule(molecule, entries.iter().map(|entry| entry.id.clone()).collect())),
+            };
+            let name = display_name(&entry.appearances);
+            let puzzle = puzzle_file::sandbox_puzzle(&name, &entry.molecule).ok_or(Error::PuzzleFile)?;
+            std::fs::write(output.unwrap_or_else(|| PathBuf::from(format!("{}.puzzle", name.replace(['/', '\\'], "_")))), puzzle)?;
+        }
+        Some(Subcommand::ExportCatalogue { format: ExportFormat::Json, output }) => std::fs::write(output, serde_json::to_vec_pretty(&export::json())?)?,
+        Some(Subcommand::ExportCatalogue { format: ExportFormat::Csv, output }) => {
+            std::fs::create_dir_all(&output)?;
+            for table in all::<export::CsvTable>() {
+                std::fs::write(output.join(table.file_name()), export::csv(table))?;
+            }
+        }
+        Some(Subcommand::ExportCatalogue { format: ExportFormat::Sqlite, output }) => export::sqlite(&output)?,
+        Some(Subcommand::AddEntry { molecule, puzzle, role, name, source, catalogue, changelog, puzzles }) => {
+            let molecule = molecule_from_notation(&molecule).ok_or(Error::InvalidNotation(molecule))?.normalized();
+            let catalogue_source = std::fs::read_to_string(&catalogue)?;
+            let changelog_source = std::fs::read_to_string(&changelog)?;
+            let puzzles_source = std::fs::read_to_string(&puzzles)?;
+            let new_puzzles_source = catalogue::add_source(&puzzles_source, &puzzle, source)?;
+            let new_catalogue_source = catalogue::add_appearance(&catalogue_source, &molecule, &puzzle, role, &name)?;
+            let new_changelog_source = catalogue::add_to_changelog(&changelog_source, &puzzle, &name, Utc::now().date_naive())?;
+            let new_changelog_source = catalogue::bump_version(&new_changelog_source, &new_catalogue_source)?;
+            std::fs::write(&catalogue, new_catalogue_source)?;
+            std::fs::write(&changelog, new_changelog_source)?;
+            if let Some(new_puzzles_source) = new_puzzles_source {
+                std::fs::write(&puzzles, new_puzzles_source)?;
+            }
+        }
+        Some(Subcommand::DiffCatalogue { from, to }) => {
+            let from = serde_json::from_slice::<diff::Snapshot>(&std::fs::read(from)?)?;
+            let to = if let Some(to) = to { serde_json::from_slice(&std::fs::read(to)?)? } else { diff::Snapshot::current() };
+            println!("{}", serde_json::to_string_pretty(&diff::diff(&from, &to))?);
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,159 @@
+use {
+    std::{
+        collections::BTreeMap,
+        fmt::{
+            self,
+            Write as _,
+        },
+        sync::{
+            Mutex,
+            atomic::{
+                AtomicU64,
+                Ordering::Relaxed,
+            },
+        },
+        time::{
+            Duration,
+            Instant,
+        },
+    },
+    chrono::prelude::*,
+    rocket::{
+        Data,
+        Request,
+        Response,
+        fairing::{
+            Fairing,
+            Info,
+            Kind,
+        },
+    },
+    crate::{
+        changelog::CATALOGUE_VERSION,
+        stats::Stats,
+    },
+};
+
+/// Upper bounds of the request latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 8] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+#[derive(Default)]
+struct Latencies {
+    /// For each bucket in [`LATENCY_BUCKETS`], the number of requests which took at most that long.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: Duration,
+}
+
+/// Request counts and latencies, lookup results, and the server's start time, reported at `/metrics`.
+pub(crate) struct Metrics {
+    started: DateTime<Utc>,
+    /// Request counts by route name, method, and status code.
+    requests: Mutex<BTreeMap<(String, &'static str, u16), u64>>,
+    /// Request latencies by route name and method.
+    latencies: Mutex<BTreeMap<(String, &'static str), Latencies>>,
+    lookup_hits: AtomicU64,
+    lookup_misses: AtomicU64,
+}
+
+impl Metrics {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Utc::now(),
+            requests: Mutex::default(),
+            latencies: Mutex::default(),
+            lookup_hits: AtomicU64::default(),
+            lookup_misses: AtomicU64::default(),
+        }
+    }
+
+    /// Counts a lookup of an exact molecule, i.e. one without wildcards.
+    pub(crate) fn record_lookup(&self, known: bool) {
+        let counter = if known { &self.lookup_hits } else { &self.lookup_misses };
+        counter.fetch_add(1, Relaxed);
+    }
+
+    fn record_request(&self, request: &Request<'_>, response: &Response<'_>, duration: Duration) {
+        let route = request.route().and_then(|route| route.name.as_deref()).unwrap_or("unmatched").to_owned();
+        let method = request.method().as_str();
+        *self.requests.lock().expect("metrics poisoned").entry((route.clone(), method, response.status().code)).or_default() += 1;
+        let mut latencies = self.latencies.lock().expect("metrics poisoned");
+        let latencies = latencies.entry((route, method)).or_default();
+        for (count, &bound) in latencies.buckets.iter_mut().zip(&LATENCY_BUCKETS) {
+            if duration.as_secs_f64() <= bound {
+                *count += 1;
+            }
+        }
+        latencies.count += 1;
+        latencies.sum += duration;
+    }
+
+    /// The metrics in the Prometheus text format.
+    pub(crate) fn render(&self, stats: &Stats) -> String {
+        let mut buf = String::default();
+        self.write(&mut buf, stats).expect("writing to a String can't fail");
+        buf
+    }
+
+    fn write(&self, buf: &mut String, stats: &Stats) -> fmt::Result {
+        writeln!(buf, "# HELP molecule_db_requests_total HTTP requests by route, method, and status code.")?;
+        writeln!(buf, "# TYPE molecule_db_requests_total counter")?;
+        for ((route, method, status), count) in &*self.requests.lock().expect("metrics poisoned") {
+            writeln!(buf, "molecule_db_requests_total{{route=\"{route}\",method=\"{method}\",status=\"{status}\"}} {count}")?;
+        }
+        writeln!(buf, "# HELP molecule_db_request_duration_seconds Time taken to respond to HTTP requests by route and method.")?;
+        writeln!(buf, "# TYPE molecule_db_request_duration_seconds histogram")?;
+        for ((route, method), latencies) in &*self.latencies.lock().expect("metrics poisoned") {
+            for (count, bound) in latencies.buckets.iter().zip(LATENCY_BUCKETS) {
+                writeln!(buf, "molecule_db_request_duration_seconds_bucket{{route=\"{route}\",method=\"{method}\",le=\"{bound}\"}} {count}")?;
+            }
+            writeln!(buf, "molecule_db_request_duration_seconds_bucket{{route=\"{route}\",method=\"{method}\",le=\"+Inf\"}} {}", latencies.count)?;
+            writeln!(buf, "molecule_db_request_duration_seconds_sum{{route=\"{route}\",method=\"{method}\"}} {}", latencies.sum.as_secs_f64())?;
+            writeln!(buf, "molecule_db_request_duration_seconds_count{{route=\"{route}\",method=\"{method}\"}} {}", latencies.count)?;
+        }
+        writeln!(buf, "# HELP molecule_db_lookups_total Lookups of molecules without wildcards, by whether the molecule is in the catalogue.")?;
+        writeln!(buf, "# TYPE molecule_db_lookups_total counter")?;
+        writeln!(buf, "molecule_db_lookups_total{{result=\"hit\"}} {}", self.lookup_hits.load(Relaxed))?;
+        writeln!(buf, "molecule_db_lookups_total{{result=\"miss\"}} {}", self.lookup_misses.load(Relaxed))?;
+        writeln!(buf, "# HELP molecule_db_catalogue_molecules Number of molecules in the catalogue.")?;
+        writeln!(buf, "# TYPE molecule_db_catalogue_molecules gauge")?;
+        writeln!(buf, "molecule_db_catalogue_molecules {}", stats.molecules)?;
+        writeln!(buf, "# HELP molecule_db_catalogue_appearances Number of appearances of molecules in puzzles in the catalogue.")?;
+        writeln!(buf, "# TYPE molecule_db_catalogue_appearances gauge")?;
+        writeln!(buf, "molecule_db_catalogue_appearances {}", stats.appearances_by_source.iter().map(|(_, count)| count).sum::<usize>())?;
+        writeln!(buf, "# HELP molecule_db_catalogue_version Version of the catalogue, incremented whenever it changes.")?;
+        writeln!(buf, "# TYPE molecule_db_catalogue_version gauge")?;
+        writeln!(buf, "molecule_db_catalogue_version {CATALOGUE_VERSION}")?;
+        writeln!(buf, "# HELP molecule_db_start_time_seconds When the server was started, in seconds since the Unix epoch.")?;
+        writeln!(buf, "# TYPE molecule_db_start_time_seconds gauge")?;
+        writeln!(buf, "molecule_db_start_time_seconds {}", self.started.timestamp())?;
+        Ok(())
+    }
+}
+
+/// When the current request was received, used to measure its latency.
+struct RequestStart(Instant);
+
+/// Records the count and latency of every request in the managed [`Metrics`].
+pub(crate) struct RequestMetrics;
+
+#[rocket::async_trait]
+impl Fairing for RequestMetrics {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        request.local_cache(|| RequestStart(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let RequestStart(start) = request.local_cache(|| RequestStart(Instant::now()));
+        if let Some(metrics) = request.rocket().state::<Metrics>() {
+            metrics.record_request(request, response, start.elapsed());
+        }
+    }
+}
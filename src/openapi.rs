@@ -0,0 +1,269 @@
+use {
+    itertools::Itertools as _,
+    rocket::Route,
+    schemars::{
+        JsonSchema,
+        gen::{
+            SchemaGenerator,
+            SchemaSettings,
+        },
+        schema::{
+            Schema,
+            SchemaObject,
+            SingleOrVec,
+            SubschemaValidation,
+        },
+        visit::{
+            self,
+            Visitor,
+        },
+    },
+    serde_json::{
+        Map,
+        Value,
+        json,
+    },
+    crate::{
+        ApiError,
+        Appearances,
+        BatchResult,
+        InOut,
+        JsState,
+        MoleculeResponse,
+        PuzzleResponse,
+        SortBy,
+        base_path,
+        diff,
+        export,
+        input,
+        limits,
+        puzzles::Source,
+        stats,
+    },
+};
+
+/// OpenAPI 3.0 doesn't support tuple schemas, so the schemas of tuples like [`Appearances`] are replaced with arrays whose items can be any of the tuple's elements.
+#[derive(Debug, Clone)]
+struct TupleItems;
+
+impl Visitor for TupleItems {
+    fn visit_schema_object(&mut self, schema: &mut SchemaObject) {
+        if let Some(ref mut array) = schema.array {
+            if let Some(SingleOrVec::Vec(ref items)) = array.items {
+                array.items = Some(SingleOrVec::Single(Box::new(Schema::Object(SchemaObject {
+                    subschemas: Some(Box::new(SubschemaValidation { any_of: Some(items.clone()), ..SubschemaValidation::default() })),
+                    ..SchemaObject::default()
+                }))));
+            }
+        }
+        visit::visit_schema_object(self, schema);
+    }
+}
+
+fn json_content<T: JsonSchema>(generator: &mut SchemaGenerator) -> Value {
+    json!({ "application/json": { "schema": generator.subschema_for::<T>() } })
+}
+
+fn content(media_type: &str) -> Value {
+    json!({ media_type: {} })
+}
+
+fn ok(content: Value) -> Value {
+    json!({ "200": { "description": "OK", "content": content } })
+}
+
+/// The request body and responses of the route with the given handler name, or `None` if the route isn't described yet.
+fn describe(generator: &mut SchemaGenerator, name: &str) -> Option<(Option<Value>, Value)> {
+    Some(match name {
+        "molecule_from_state" => (Some(json_content::<JsState>(generator)), ok(json_content::<MoleculeResponse>(generator))),
+        "batch_lookup" => (Some(json_content::<Vec<input::MoleculeInput>>(generator)), ok(json_content::<Vec<BatchResult>>(generator))),
+        "lookup_v2" => {
+            let request = json_content::<input::StructuredMolecule>(generator);
+            let error = json_content::<ApiError>(generator);
+            (Some(request), json!({
+                "200": { "description": "OK", "content": json_content::<MoleculeResponse>(generator) },
+                "400": { "description": "The request body couldn't be read", "content": error },
+                "422": { "description": "The molecule is invalid", "content": error },
+            }))
+        }
+        "sandbox_puzzle_from_state" => (Some(json_content::<JsState>(generator)), ok(content("application/octet-stream"))),
+        "shapes_json" => (None, ok(json_content::<Vec<Vec<Appearances>>>(generator))),
+        "puzzle_json" => (None, ok(json_content::<PuzzleResponse<'static>>(generator))),
+        "stats_json" => (None, ok(json_content::<stats::Stats>(generator))),
+        "catalogue_diff" => {
+            let request = json_content::<diff::DiffRequest>(generator);
+            let error = json_content::<ApiError>(generator);
+            (Some(request), json!({
+                "200": { "description": "OK", "content": json_content::<diff::Diff>(generator) },
+                "400": { "description": "The request body couldn't be read", "content": error },
+                "422": { "description": "The request body isn't a catalogue export or a pair of them", "content": error },
+            }))
+        }
+        "export_json" => (None, ok(json_content::<export::CatalogueJson>(generator))),
+        "openapi_json" => (None, ok(content("application/json"))),
+        "molecule_image" => (None, ok(content("image/svg+xml"))),
+        "molecule_sandbox_puzzle" => (None, ok(content("application/octet-stream"))),
+        "export_sqlite" => (None, ok(content("application/vnd.sqlite3"))),
+        "export_csv" => (None, ok(content("text/csv"))),
+        "recent_atom" => (None, ok(content("application/atom+xml"))),
+        "approved_submissions" | "metrics_endpoint" => (None, ok(content("text/plain"))),
+        "healthz" => (None, json!({
+            "200": { "description": "OK", "content": content("text/plain") },
+            "503": { "description": "A configured database is unavailable", "content": content("text/plain") },
+        })),
+        "common_css" => (None, ok(content("text/css"))),
+        "common_js" | "transmogrification_js" => (None, ok(content("text/javascript"))),
+        "submit_molecule" => (Some(content("application/x-www-form-urlencoded")), ok(content("text/html"))),
+        "admin_login" => (Some(content("application/x-www-form-urlencoded")), json!({
+            "303": { "description": "Logged in" },
+            "401": { "description": "Wrong admin token" },
+            "429": { "description": "Too many login attempts", "headers": { "Retry-After": { "schema": { "type": "integer" } } }, "content": json_content::<ApiError>(generator) },
+        })),
+        "approve_submission" | "reject_submission" => (None, json!({ "303": { "description": "Reviewed" } })),
+        "index" | "lookup" | "molecules_list" | "molecule_page" | "shapes" | "puzzles_list" | "puzzle_page" | "stats_page" | "recent" | "admin_login_page" | "unknown_molecules" | "admin_submissions" => (None, ok(content("text/html"))),
+        _ => return None,
+    })
+}
+
+fn query_parameter_schema(generator: &mut SchemaGenerator, name: &str) -> Value {
+    match name {
+        "source" => json!(generator.subschema_for::<Source>()),
+        "role" => json!(generator.subschema_for::<InOut>()),
+        "sort" => json!(generator.subschema_for::<SortBy>()),
+        "min_atoms" | "max_atoms" | "page" => json!({ "type": "integer", "minimum": 0 }),
+        _ => json!({ "type": "string" }),
+    }
+}
+
+/// An OpenAPI document describing the given routes.
+pub(crate) fn spec<'a>(routes: impl Iterator<Item = &'a Route>) -> Value {
+    let mut settings = SchemaSettings::openapi3();
+    settings.visitors.push(Box::new(TupleItems));
+    let mut generator = settings.into_generator();
+    let mut paths = Map::default();
+    for route in routes {
+        let uri = route.uri.unmounted();
+        let mut parameters = Vec::default();
+        let path = uri.path().as_str().split('/').map(|segment| if let Some(name) = segment.strip_prefix('<').and_then(|segment| segment.strip_suffix('>')) {
+            let name = name.trim_end_matches("..");
+            parameters.push(json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }));
+            format!("{{{name}}}")
+        } else {
+            segment.to_owned()
+        }).join("/");
+        if let Some(query) = uri.query() {
+            for name in query.as_str().split('&').filter_map(|segment| segment.strip_prefix('<')?.strip_suffix('>')) {
+                let name = name.trim_end_matches("..");
+                parameters.push(json!({ "name": name, "in": "query", "required": false, "schema": query_parameter_schema(&mut generator, name) }));
+            }
+        }
+        let name = route.name.as_deref().unwrap_or_default();
+        let (request_body, mut responses) = describe(&mut generator, name).unwrap_or_else(|| (None, json!({ "default": { "description": "Not described yet" } })));
+        if parameters.iter().any(|parameter| parameter["in"] == "path") {
+            responses["404"] = json!({ "description": "Not Found" });
+        }
+        if limits::LIMITED_ROUTES.contains(&name) {
+            let error = json_content::<ApiError>(&mut generator);
+            if request_body.is_some() {
+                responses["413"] = json!({ "description": "The request body, the molecule, or the batch is too large", "content": error });
+            }
+            responses["429"] = json!({ "description": "The client has exceeded the rate limit", "headers": { "Retry-After": { "schema": { "type": "integer" } } }, "content": error });
+        }
+        let mut operation = json!({ "operationId": name, "parameters": parameters, "responses": responses });
+        if let Some(content) = request_body {
+            operation["requestBody"] = json!({ "required": true, "content": content });
+        }
+        paths.entry(path).or_insert_with(|| json!({}))[route.method.as_str().to_lowercase()] = operation;
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Opus Magnum Molecule Database",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{ "url": if base_path().is_empty() { "/" } else { base_path() } }],
+        "paths": paths,
+        "components": {
+            "schemas": generator.take_definitions(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        rocket::{
+            http::{
+                Method,
+                Status,
+            },
+            local::blocking::Client,
+        },
+        serde_json::Value,
+        crate::Settings,
+        super::*,
+    };
+
+    fn refs<'a>(value: &'a Value, found: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(object) => for (key, value) in object {
+                if let (Some(reference), "$ref") = (value.as_str(), &**key) {
+                    found.push(reference);
+                } else {
+                    refs(value, found);
+                }
+            },
+            Value::Array(array) => for value in array {
+                refs(value, found);
+            },
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn served_spec_validates() {
+        let client = Client::tracked(crate::rocket(rocket::Config::figment(), Settings::default()).expect("failed to configure server")).expect("failed to start server");
+        let response = client.get("/api/openapi.json").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body = response.into_string().expect("missing response body");
+        let spec = serde_json::from_str::<openapiv3::OpenAPI>(&body).expect("not a valid OpenAPI document");
+        for route in client.rocket().routes() {
+            let path = route.uri.unmounted().path().as_str().split('/').map(|segment| if segment.starts_with('<') {
+                format!("{{{}}}", segment.trim_start_matches('<').trim_end_matches('>').trim_end_matches(".."))
+            } else {
+                segment.to_owned()
+            }).collect::<Vec<_>>().join("/");
+            assert!(spec.paths.paths.contains_key(&path), "route {path} is missing from the spec");
+        }
+        let schemas = spec.components.expect("missing components").schemas;
+        let body = serde_json::from_str::<Value>(&body).expect("not valid JSON");
+        let mut found = Vec::default();
+        refs(&body, &mut found);
+        assert!(!found.is_empty());
+        for reference in found {
+            let name = reference.strip_prefix("#/components/schemas/").unwrap_or_else(|| panic!("unexpected reference {reference}"));
+            assert!(schemas.contains_key(name), "schema {name} is referenced but not defined");
+        }
+    }
+
+    #[test]
+    fn routes_match_descriptions() {
+        let client = Client::tracked(crate::rocket(rocket::Config::figment(), Settings::default()).expect("failed to configure server")).expect("failed to start server");
+        let mut generator = SchemaSettings::openapi3().into_generator();
+        for route in client.rocket().routes() {
+            let name = route.name.as_deref().unwrap_or_default();
+            let (_, responses) = describe(&mut generator, name).unwrap_or_else(|| panic!("route {name} isn't described"));
+            // check the documented media type against the actual one for routes that can be requested without parameters
+            if route.method != Method::Get || route.uri.path().as_str().contains('<') {
+                continue
+            }
+            let response = client.get(route.uri.path().as_str()).dispatch();
+            if response.status() != Status::Ok {
+                continue
+            }
+            let content_type = response.content_type().unwrap_or_else(|| panic!("route {name} responded without a content type"));
+            let media_type = format!("{}/{}", content_type.top(), content_type.sub());
+            assert!(responses["200"]["content"].get(&media_type).is_some(), "route {name} responded with {media_type}, which isn't described");
+        }
+    }
+}
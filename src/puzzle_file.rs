@@ -0,0 +1,213 @@
+use {
+    std::collections::HashSet,
+    itertools::Itertools as _,
+    omsim_rs::data::*,
+    crate::glyphs::ATOMS,
+};
+
+const VERSION: u32 = 3;
+
+// flags for the parts a puzzle allows, see `decode_puzzle` in omsim's parse.c
+const ARMS: u64 = 1 << 0;
+const MULTI_ARMS: u64 = 1 << 1;
+const PISTON: u64 = 1 << 2;
+const TRACK: u64 = 1 << 3;
+const BONDER: u64 = 1 << 8;
+const UNBONDER: u64 = 1 << 9;
+const MULTI_BONDER: u64 = 1 << 10;
+const TRIPLEX_BONDER: u64 = 1 << 11;
+const CALCIFICATION: u64 = 1 << 12;
+const DUPLICATION: u64 = 1 << 13;
+const PROJECTION: u64 = 1 << 14;
+const PURIFICATION: u64 = 1 << 15;
+const ANIMISMUS: u64 = 1 << 16;
+const DISPOSAL: u64 = 1 << 17;
+const QUINTESSENCE_GLYPHS: u64 = 1 << 18;
+const GRAB_AND_TURN: u64 = 1 << 22;
+const DROP: u64 = 1 << 23;
+const RESET: u64 = 1 << 24;
+const REPEAT: u64 = 1 << 25;
+const PIVOT: u64 = 1 << 26;
+const VAN_BERLOS_WHEEL: u64 = 1 << 28;
+
+/// Everything except the computation parts, like in the in-game sandbox.
+const SANDBOX_PARTS: u64 = ARMS | MULTI_ARMS | PISTON | TRACK
+    | BONDER | UNBONDER | MULTI_BONDER | TRIPLEX_BONDER
+    | CALCIFICATION | DUPLICATION | PROJECTION | PURIFICATION | ANIMISMUS | DISPOSAL | QUINTESSENCE_GLYPHS
+    | GRAB_AND_TURN | DROP | RESET | REPEAT | PIVOT
+    | VAN_BERLOS_WHEEL;
+
+fn write_string(buf: &mut Vec<u8>, s: &str) {
+    let mut len = s.len();
+    loop {
+        let byte = (len & 0x7f) as u8;
+        len >>= 7;
+        if len == 0 {
+            buf.push(byte);
+            break
+        }
+        buf.push(byte | 0x80);
+    }
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn atom_type(atom: Atom) -> u8 {
+    match atom {
+        Atom::Salt => 1,
+        Atom::Air => 2,
+        Atom::Earth => 3,
+        Atom::Fire => 4,
+        Atom::Water => 5,
+        Atom::Quicksilver => 6,
+        Atom::Gold => 7,
+        Atom::Silver => 8,
+        Atom::Copper => 9,
+        Atom::Iron => 10,
+        Atom::Tin => 11,
+        Atom::Lead => 12,
+        Atom::Vitae => 13,
+        Atom::Mors => 14,
+        Atom::Repeat => 15,
+        Atom::Quintessence => 16,
+    }
+}
+
+fn bond_type(ty: BondType) -> u8 {
+    match ty {
+        BondType::Normal => 1,
+        BondType::Triplex { red, black, yellow } => (u8::from(red) << 1) | (u8::from(black) << 2) | (u8::from(yellow) << 3),
+    }
+}
+
+/// Returns `None` if a coordinate doesn't fit into a byte.
+fn write_molecule(buf: &mut Vec<u8>, molecule: &Molecule) -> Option<()> {
+    buf.extend_from_slice(&u32::try_from(molecule.atoms.len()).ok()?.to_le_bytes());
+    for (pos, &atom) in molecule.atoms.iter().sorted_by_key(|(pos, _)| (pos.q, pos.r)) {
+        buf.push(atom_type(atom));
+        buf.push(i8::try_from(pos.q).ok()? as u8);
+        buf.push(i8::try_from(pos.r).ok()? as u8);
+    }
+    buf.extend_from_slice(&u32::try_from(molecule.bonds.len()).ok()?.to_le_bytes());
+    for bond in molecule.bonds.iter().sorted_by_key(|bond| (bond.start.q, bond.start.r, bond.end.q, bond.end.r)) {
+        buf.push(bond_type(bond.ty));
+        buf.push(i8::try_from(bond.start.q).ok()? as u8);
+        buf.push(i8::try_from(bond.start.r).ok()? as u8);
+        buf.push(i8::try_from(bond.end.q).ok()? as u8);
+        buf.push(i8::try_from(bond.end.r).ok()? as u8);
+    }
+    Some(())
+}
+
+/// A sandbox puzzle with the given molecule as its only product and a single-atom reagent for each element the molecule contains.
+///
+/// See <https://github.com/ianh/omsim/blob/main/parse.c> for the file format.
+/// Returns `None` for polymers and molecules too large to be represented in a puzzle file.
+pub(crate) fn sandbox_puzzle(name: &str, molecule: &Molecule) -> Option<Vec<u8>> {
+    if molecule.atoms.is_empty() || molecule.atoms.values().any(|&atom| atom == Atom::Repeat) {
+        return None
+    }
+    let reagents = ATOMS.into_iter()
+        .filter(|atom| molecule.atoms.values().contains(atom))
+        .map(|atom| Molecule { atoms: [(HexIndex::default(), atom)].into_iter().collect(), bonds: HashSet::default() })
+        .collect_vec();
+    let mut buf = Vec::default();
+    buf.extend_from_slice(&VERSION.to_le_bytes());
+    write_string(&mut buf, name);
+    buf.extend_from_slice(&0u64.to_le_bytes()); // creator
+    buf.extend_from_slice(&SANDBOX_PARTS.to_le_bytes());
+    buf.extend_from_slice(&u32::try_from(reagents.len()).ok()?.to_le_bytes());
+    for reagent in &reagents {
+        write_molecule(&mut buf, reagent)?;
+    }
+    buf.extend_from_slice(&1u32.to_le_bytes());
+    write_molecule(&mut buf, molecule)?;
+    buf.extend_from_slice(&1u32.to_le_bytes()); // output scale
+    buf.push(0); // not a production puzzle
+    Some(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::molecules,
+        super::*,
+    };
+
+    /// Reads back the parts of a puzzle file written by [`sandbox_puzzle`].
+    struct Reader<'a>(&'a [u8]);
+
+    impl Reader<'_> {
+        fn bytes<const N: usize>(&mut self) -> [u8; N] {
+            let (head, tail) = self.0.split_first_chunk().expect("unexpected end of puzzle file");
+            self.0 = tail;
+            *head
+        }
+
+        fn u8(&mut self) -> u8 { u8::from_le_bytes(self.bytes()) }
+        fn i8(&mut self) -> i8 { i8::from_le_bytes(self.bytes()) }
+        fn u32(&mut self) -> u32 { u32::from_le_bytes(self.bytes()) }
+        fn u64(&mut self) -> u64 { u64::from_le_bytes(self.bytes()) }
+
+        fn string(&mut self) -> String {
+            let mut len = 0;
+            let mut shift = 0;
+            loop {
+                let byte = self.u8();
+                len |= usize::from(byte & 0x7f) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 { break }
+            }
+            let (s, tail) = self.0.split_at(len);
+            self.0 = tail;
+            String::from_utf8(s.to_owned()).expect("puzzle name is not UTF-8")
+        }
+
+        fn pos(&mut self) -> HexIndex {
+            HexIndex { q: self.i8().into(), r: self.i8().into() }
+        }
+
+        fn molecule(&mut self) -> Molecule {
+            let atoms = (0..self.u32()).map(|_| {
+                let ty = self.u8();
+                let atom = ATOMS.into_iter().find(|&atom| atom_type(atom) == ty).expect("unknown atom type");
+                (self.pos(), atom)
+            }).collect();
+            let bonds = (0..self.u32()).map(|_| {
+                let ty = match self.u8() {
+                    1 => BondType::Normal,
+                    ty => BondType::Triplex { red: ty & 2 != 0, black: ty & 4 != 0, yellow: ty & 8 != 0 },
+                };
+                Bond { start: self.pos(), end: self.pos(), ty }
+            }).collect();
+            Molecule { atoms, bonds }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for (molecule, appearances) in molecules::molecules() {
+            let (_, _, name) = appearances[0];
+            let Some(file) = sandbox_puzzle(name, &molecule) else {
+                assert!(molecule.atoms.values().any(|&atom| atom == Atom::Repeat), "no puzzle file for {name}");
+                continue
+            };
+            let mut reader = Reader(&file);
+            assert_eq!(reader.u32(), VERSION);
+            assert_eq!(reader.string(), name);
+            assert_eq!(reader.u64(), 0);
+            assert_eq!(reader.u64(), SANDBOX_PARTS);
+            let reagents = (0..reader.u32()).map(|_| reader.molecule()).collect_vec();
+            assert!(reagents.iter().all(|reagent| reagent.atoms.len() == 1 && reagent.bonds.is_empty()), "reagents of {name} aren't single atoms");
+            assert_eq!(
+                reagents.iter().flat_map(|reagent| reagent.atoms.values().map(|&atom| atom_type(atom))).collect::<HashSet<_>>(),
+                molecule.atoms.values().map(|&atom| atom_type(atom)).collect::<HashSet<_>>(),
+                "reagents of {name} don't match its elements",
+            );
+            assert_eq!(reader.u32(), 1);
+            assert!(reader.molecule() == molecule, "product of {name} doesn't match");
+            assert_eq!(reader.u32(), 1);
+            assert_eq!(reader.u8(), 0);
+            assert!(reader.0.is_empty(), "trailing data in puzzle file for {name}");
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use {
+    std::sync::OnceLock,
+    enum_iterator::Sequence,
+    omsim_rs::data::*,
+    schemars::JsonSchema,
+    serde::Serialize,
+    crate::{
+        InOut,
+        glyphs::{
+            self,
+            GlyphAnalysis,
+        },
+        molecules,
+        ratios::{
+            self,
+            Ratio,
+        },
+    },
+};
+
+/// The source of each puzzle in the catalogue, sorted by name. Weekly puzzles are marked with a `*` suffix instead.
+pub(crate) const SOURCES: [(&str, Source); 132] = [
+    ("4 Suits", Source::Tournament),
+    ("Ablative Crystal", Source::Journal),
+    ("Abrasive Particles", Source::Journal),
+    ("Aether Detector", Source::Journal),
+    ("Airship Fuel", Source::Campaign),
+    ("Alchemical Jewel", Source::Journal),
+    ("Alchemical Slag", Source::Journal),
+    ("Alcohol Separation", Source::Journal),
+    ("Animismus Buffer", Source::Journal),
+    ("Armor Filament", Source::Campaign),
+    ("Assassin's Filament", Source::Campaign),
+    ("Blood-Stanching Powder", Source::Campaign),
+    ("Boundary of Life and Death", Source::Tournament),
+    ("Buoyant Cable", Source::Campaign),
+    ("Cardinal Tris", Source::Tournament),
+    ("Celestial Thread", Source::Campaign),
+    ("Chloroplast Reduction", Source::Tournament),
+    ("Chloroplast Reduction [Ex.]", Source::Tournament),
+    ("Climbing Rope Fiber", Source::Campaign),
+    ("Conductive Enamel", Source::Campaign),
+    ("Correcting Chirality", Source::Tournament),
+    ("Courage Potion", Source::Campaign),
+    ("Curious Lipstick", Source::Journal),
+    ("Electrum Separation", Source::Journal),
+    ("Embalming Fluid", Source::Campaign),
+    ("Explorer's Salve", Source::Campaign),
+    ("Explosive Gel", Source::Tournament),
+    ("Explosive Phial", Source::Campaign),
+    ("Explosive Victrite", Source::Campaign),
+    ("Eyedrops of Revelation", Source::Campaign),
+    ("Face Powder", Source::Campaign),
+    ("Flash Cooling Liquid", Source::Tournament),
+    ("Forager Recomposition", Source::Tournament),
+    ("Fragrant Powders", Source::Journal),
+    ("General Anaesthetic", Source::Campaign),
+    ("Golden Thread", Source::Campaign),
+    ("Greed to Gold", Source::Tournament),
+    ("Hair Product", Source::Campaign),
+    ("Hangover Cure", Source::Campaign),
+    ("Health Tonic", Source::Campaign),
+    ("Hexstabilized Salt", Source::Journal),
+    ("Human DNA to LOL Player DNA", Source::Tournament),
+    ("Hyper-volatile Gas", Source::Campaign),
+    ("Hyper-volatile gas", Source::Campaign),
+    ("Invisible I-- Oops", Source::Tournament),
+    ("Invisible Ink", Source::Campaign),
+    ("Jester's Regestion", Source::Tournament),
+    ("Jigsaw's Wire", Source::Tournament),
+    ("Lambent II/IX", Source::Journal),
+    ("Lamplight Gas", Source::Campaign),
+    ("Lesson: Arms", Source::Campaign),
+    ("Lesson: Bonding", Source::Campaign),
+    ("Lesson: Introduction", Source::Campaign),
+    ("Lesson: Pistons", Source::Campaign),
+    ("Lesson: Pivots", Source::Campaign),
+    ("Lesson: Tracks", Source::Campaign),
+    ("Lesson: Transmutation", Source::Campaign),
+    ("Life-Sensing Potion", Source::Campaign),
+    ("Lightbulb Oil", Source::Tournament),
+    ("Litharge Separation", Source::Journal),
+    ("Lubricating Filament", Source::Campaign),
+    ("Lustre", Source::Journal),
+    ("Mist of Hallucination", Source::Campaign),
+    ("Mist of Incapacitation", Source::Campaign),
+    ("On the Properties of the Universal Reagent I", Source::Tournament),
+    ("On the Properties of the Universal Reagent II", Source::Tournament),
+    ("On the Properties of the Universal Reagent III", Source::Tournament),
+    ("On the Properties of the Universal Reagent IV", Source::Tournament),
+    ("On the Properties of the Universal Reagent V", Source::Tournament),
+    ("Parade-Rocket Fuel", Source::Campaign),
+    ("Patina Processing", Source::Tournament),
+    ("Pizza", Source::Tournament),
+    ("Precision Machine Oil", Source::Campaign),
+    ("Preservative Salt", Source::Campaign),
+    ("Proof of Completeness", Source::Journal),
+    ("Purified Gold", Source::Campaign),
+    ("Quintessential Medium", Source::Campaign),
+    ("Rat Poison", Source::Campaign),
+    ("Ravari's Wheel", Source::Journal),
+    ("Reactive Cinnabar", Source::Campaign),
+    ("Reactive Gold", Source::Journal),
+    ("Reconstructed Solvent", Source::Campaign),
+    ("Refined Bronze", Source::Journal),
+    ("Refined Gold", Source::Campaign),
+    ("Resonant Crystal", Source::Journal),
+    ("Rocket Propellant", Source::Campaign),
+    ("Rose Gold", Source::Tournament),
+    ("Sailcloth Thread", Source::Campaign),
+    ("Seal Solvent", Source::Campaign),
+    ("Silver Caustic", Source::Campaign),
+    ("Silver Paint", Source::Campaign),
+    ("Special Amaro", Source::Campaign),
+    ("Splinter of Greed", Source::Tournament),
+    ("Spyglass Crystal", Source::Journal),
+    ("Stabilized Water", Source::Campaign),
+    ("Stain Remover", Source::Campaign),
+    ("Stamina Potion", Source::Campaign),
+    ("Surrender Flare", Source::Campaign),
+    ("Suture Thread", Source::Campaign),
+    ("Sword Alloy", Source::Campaign),
+    ("Synthesis via Alcohol", Source::Campaign),
+    ("Synthetic Malachite", Source::Journal),
+    ("Thermite Filament", Source::Tournament),
+    ("Timing Crystal", Source::Journal),
+    ("Tonic of Hydration", Source::Campaign),
+    ("Touchstone", Source::Journal),
+    ("Universal Compound", Source::Campaign),
+    ("Universal Solvent", Source::Campaign),
+    ("Unstable Compound", Source::Campaign),
+    ("Van Berlo's Chain", Source::Journal),
+    ("Van Berlo's Pivots", Source::Journal),
+    ("Van Berlo's Wheel", Source::Journal),
+    ("Vanishing Material", Source::Campaign),
+    ("Vapor of Levity", Source::Campaign),
+    ("Vaporous Solvent", Source::Campaign),
+    ("Very Dark Thread", Source::Campaign),
+    ("Viscous Sludge", Source::Campaign),
+    ("Visillary Anaesthetic", Source::Campaign),
+    ("Voltaic Coil", Source::Campaign),
+    ("Wakefulness Potion", Source::Campaign),
+    ("Warming Tonic", Source::Campaign),
+    ("Water Purifier", Source::Campaign),
+    ("Waterproof Sealant", Source::Campaign),
+    ("Welding Thermite", Source::Campaign),
+    ("Wheel Representation", Source::Journal),
+    ("[Prod. Amalgam Salvage", Source::Tournament),
+    ("[Prod.] 4 to 3", Source::Tournament),
+    ("[Prod.] MSG", Source::Tournament),
+    ("[Prod.] Miniature Explosive Gel", Source::Tournament),
+    ("[Prod.] Miniature Invisible Ink", Source::Tournament),
+    ("[Prod.] Nothing Doing", Source::Tournament),
+    ("[Prod.] Quickfire", Source::Tournament),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Sequence, JsonSchema, Serialize, clap::ValueEnum, rocket::FromFormField)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum Source {
+    /// The main campaign of Opus Magnum, including its tutorials and production puzzles.
+    Campaign,
+    /// The journal puzzles of Opus Magnum.
+    Journal,
+    Tournament,
+    Weekly,
+}
+
+impl Source {
+    /// The source of a puzzle from the catalogue, or `None` if it's missing from [`SOURCES`].
+    pub(crate) fn of(puzzle_name: &str) -> Option<Self> {
+        if puzzle_name.ends_with('*') {
+            Some(Self::Weekly)
+        } else {
+            SOURCES.binary_search_by_key(&puzzle_name, |&(name, _)| name).ok().map(|idx| SOURCES[idx].1)
+        }
+    }
+
+    /// The value used for this source in query parameters and exports.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Campaign => "campaign",
+            Self::Journal => "journal",
+            Self::Tournament => "tournament",
+            Self::Weekly => "weekly",
+        }
+    }
+
+    pub(crate) fn description(&self) -> &'static str {
+        match self {
+            Self::Campaign => "the Opus Magnum campaign",
+            Self::Journal => "the Opus Magnum journal",
+            Self::Tournament => "community tournaments",
+            Self::Weekly => "community weeklies",
+        }
+    }
+}
+
+pub(crate) struct Puzzle {
+    pub(crate) name: &'static str,
+    /// `None` if the puzzle is missing from [`SOURCES`].
+    pub(crate) source: Option<Source>,
+    pub(crate) reagents: Vec<(Molecule, &'static str)>,
+    pub(crate) products: Vec<(Molecule, &'static str)>,
+}
+
+/// All puzzles mentioned in the catalogue, sorted by name.
+pub(crate) fn puzzles() -> Vec<Puzzle> {
+    let mut puzzles = Vec::<Puzzle>::default();
+    for (molecule, appearances) in molecules::molecules() {
+        for (puzzle_name, inout, name) in appearances {
+            let idx = if let Some(idx) = puzzles.iter().position(|puzzle| puzzle.name == puzzle_name) {
+                idx
+            } else {
+                puzzles.push(Puzzle { name: puzzle_name, source: Source::of(puzzle_name), reagents: Vec::default(), products: Vec::default() });
+                puzzles.len() - 1
+            };
+            let puzzle = &mut puzzles[idx];
+            if let InOut::Reagent | InOut::Both = inout {
+                puzzle.reagents.push((molecule.clone(), name));
+            }
+            if let InOut::Product | InOut::Both = inout {
+                puzzle.products.push((molecule.clone(), name));
+            }
+        }
+    }
+    puzzles.sort_unstable_by_key(|puzzle| puzzle.name);
+    puzzles
+}
+
+#[cfg(test)]
+pub(crate) fn puzzle(name: &str) -> Option<Puzzle> {
+    puzzles().into_iter().find(|puzzle| puzzle.name == name)
+}
+
+pub(crate) struct AnalyzedPuzzle {
+    pub(crate) puzzle: Puzzle,
+    analysis: OnceLock<Analysis>,
+}
+
+pub(crate) struct Analysis {
+    pub(crate) glyphs: GlyphAnalysis,
+    /// The minimal reagent ratio using the glyphs in `glyphs.sufficient`.
+    pub(crate) ratio: Option<Ratio>,
+}
+
+impl AnalyzedPuzzle {
+    /// The glyph analysis and ratio of this puzzle. Since the ratio search is expensive, this is computed when it's first needed and then kept, since the catalogue is compiled in.
+    pub(crate) fn analysis(&self) -> &Analysis {
+        self.analysis.get_or_init(|| {
+            let glyphs = glyphs::analyze(&self.puzzle);
+            let ratio = glyphs.sufficient.as_ref().and_then(|sufficient| ratios::minimal_ratio(&self.puzzle, sufficient));
+            Analysis { glyphs, ratio }
+        })
+    }
+}
+
+/// All puzzles, computed once at startup. Their analyses are only computed for the puzzles which are requested, see [`AnalyzedPuzzle::analysis`].
+pub(crate) struct Puzzles(Vec<AnalyzedPuzzle>);
+
+impl Puzzles {
+    pub(crate) fn compute() -> Self {
+        Self(puzzles().into_iter().map(|puzzle| AnalyzedPuzzle { puzzle, analysis: OnceLock::default() }).collect())
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &AnalyzedPuzzle> {
+        self.0.iter()
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&AnalyzedPuzzle> {
+        self.0.binary_search_by_key(&name, |analyzed| analyzed.puzzle.name).ok().map(|idx| &self.0[idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_sorted() {
+        assert!(SOURCES.windows(2).all(|pair| pair[0].0 < pair[1].0), "puzzles::SOURCES isn't sorted by name");
+        assert!(!SOURCES.iter().any(|(name, _)| name.ends_with('*')), "weekly puzzles don't need to be listed in puzzles::SOURCES");
+    }
+
+    #[test]
+    fn every_puzzle_has_a_source() {
+        for (_, appearances) in molecules::molecules() {
+            for (puzzle, _, _) in appearances {
+                assert!(Source::of(puzzle).is_some(), "{puzzle:?} is missing from puzzles::SOURCES");
+            }
+        }
+    }
+}
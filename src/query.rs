@@ -0,0 +1,188 @@
+use {
+    std::collections::{
+        HashMap,
+        HashSet,
+    },
+    omsim_rs::data::*,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AtomClass {
+    /// Air, earth, fire, or water.
+    Cardinal,
+    /// Lead, tin, iron, copper, silver, or gold.
+    Metal,
+    SaltOrQuicksilver,
+    VitaeOrMors,
+    /// Any atom other than the repeat marker.
+    Any,
+}
+
+impl AtomClass {
+    pub(crate) fn contains(&self, atom: Atom) -> bool {
+        match self {
+            Self::Cardinal => matches!(atom, Atom::Air | Atom::Earth | Atom::Fire | Atom::Water),
+            Self::Metal => matches!(atom, Atom::Lead | Atom::Tin | Atom::Iron | Atom::Copper | Atom::Silver | Atom::Gold),
+            Self::SaltOrQuicksilver => matches!(atom, Atom::Salt | Atom::Quicksilver),
+            Self::VitaeOrMors => matches!(atom, Atom::Vitae | Atom::Mors),
+            Self::Any => atom != Atom::Repeat,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AtomPattern {
+    Exact(Atom),
+    Class(AtomClass),
+}
+
+impl AtomPattern {
+    pub(crate) fn matches(&self, atom: Atom) -> bool {
+        match self {
+            Self::Exact(expected) => *expected == atom,
+            Self::Class(class) => class.contains(atom),
+        }
+    }
+}
+
+/// A molecule in which some atoms may be wildcards.
+pub(crate) struct Query {
+    pub(crate) atoms: HashMap<HexIndex, AtomPattern>,
+    pub(crate) bonds: HashSet<Bond>,
+}
+
+impl Query {
+    /// Returns the molecule described by this query, or `None` if it contains wildcard atoms.
+    pub(crate) fn to_molecule(&self) -> Option<Molecule> {
+        Some(Molecule {
+            atoms: self.atoms.iter().map(|(&pos, pattern)| match pattern {
+                AtomPattern::Exact(atom) => Some((pos, *atom)),
+                AtomPattern::Class(_) => None,
+            }).collect::<Option<_>>()?,
+            bonds: self.bonds.clone(),
+        })
+    }
+
+    /// The molecule with the same bonds and geometry, with every atom replaced by salt. Used to compare against [`crate::MoleculeExt::shape`].
+    pub(crate) fn skeleton(&self) -> Molecule {
+        Molecule {
+            atoms: self.atoms.iter().map(|(&pos, pattern)| (pos, if *pattern == AtomPattern::Exact(Atom::Repeat) { Atom::Repeat } else { Atom::Salt })).collect(),
+            bonds: self.bonds.clone(),
+        }
+    }
+
+    /// Checks whether the given molecule matches this query under some rotation.
+    pub(crate) fn matches(&self, molecule: &Molecule) -> bool {
+        if self.atoms.len() != molecule.atoms.len() || self.bonds.len() != molecule.bonds.len() {
+            return false
+        }
+        let target = normalize_positions(molecule.atoms.iter().map(|(&pos, &atom)| (pos, atom)).collect(), &molecule.bonds);
+        let rotations = if self.atoms.values().any(|&pattern| pattern == AtomPattern::Exact(Atom::Repeat)) { 1 } else { 6 };
+        let mut atoms = self.atoms.clone();
+        let mut bonds = self.bonds.clone();
+        for _ in 0..rotations {
+            let (query_atoms, query_bonds) = normalize_positions(atoms.clone(), &bonds);
+            if query_bonds == target.1 && query_atoms.iter().all(|(pos, pattern)| target.0.get(pos).is_some_and(|&atom| pattern.matches(atom))) {
+                return true
+            }
+            atoms = atoms.into_iter().map(|(pos, pattern)| (rotate_ccw(pos), pattern)).collect();
+            bonds = bonds.into_iter().map(|Bond { start, end, ty }| Bond { start: rotate_ccw(start), end: rotate_ccw(end), ty }).collect();
+        }
+        false
+    }
+
+    /// Checks whether the given molecule contains this query as a part under some rotation, i.e. whether the query's atoms can be mapped onto matching atoms of the molecule such that each of the query's bonds is also a bond of the molecule. Unlike [`Query::matches`], the molecule may have additional atoms and bonds.
+    pub(crate) fn is_part_of(&self, molecule: &Molecule) -> bool {
+        if self.atoms.is_empty() || self.atoms.len() > molecule.atoms.len() || self.bonds.len() > molecule.bonds.len() {
+            return false
+        }
+        let rotations = if self.atoms.values().any(|&pattern| pattern == AtomPattern::Exact(Atom::Repeat)) { 1 } else { 6 };
+        let mut atoms = self.atoms.clone();
+        let mut bonds = self.bonds.clone();
+        for _ in 0..rotations {
+            let anchor = *atoms.keys().next().expect("checked above");
+            // try every position of the molecule as the image of the anchor atom
+            for &target in molecule.atoms.keys() {
+                let offset = anchor - target;
+                if atoms.iter().all(|(&pos, pattern)| molecule.atoms.get(&(pos - offset)).is_some_and(|&atom| pattern.matches(atom)))
+                && bonds.iter().all(|&Bond { start, end, ty }| {
+                    let (start, end) = (start - offset, end - offset);
+                    molecule.bonds.contains(&Bond { start, end, ty }) || molecule.bonds.contains(&Bond { start: end, end: start, ty })
+                }) {
+                    return true
+                }
+            }
+            atoms = atoms.into_iter().map(|(pos, pattern)| (rotate_ccw(pos), pattern)).collect();
+            bonds = bonds.into_iter().map(|Bond { start, end, ty }| Bond { start: rotate_ccw(start), end: rotate_ccw(end), ty }).collect();
+        }
+        false
+    }
+}
+
+/// Rotates a position by 60° counterclockwise around the origin.
+pub(crate) fn rotate_ccw(HexIndex { q, r }: HexIndex) -> HexIndex {
+    HexIndex { q: -r, r: q + r }
+}
+
+/// The minimal q and r coordinates of the given positions, which position normalization translates to 0.
+pub(crate) fn position_offset<'a>(positions: impl IntoIterator<Item = &'a HexIndex> + Clone) -> HexIndex {
+    HexIndex {
+        q: positions.clone().into_iter().map(|&HexIndex { q, .. }| q).min().unwrap_or_default(),
+        r: positions.into_iter().map(|&HexIndex { r, .. }| r).min().unwrap_or_default(),
+    }
+}
+
+/// Translates atoms and bonds so the minimal coordinates are 0 and orders each bond's endpoints, like [`crate::MoleculeExt::position_normalized`].
+fn normalize_positions<T>(atoms: HashMap<HexIndex, T>, bonds: &HashSet<Bond>) -> (HashMap<HexIndex, T>, HashSet<Bond>) {
+    let offset = position_offset(atoms.keys());
+    (
+        atoms.into_iter().map(|(pos, atom)| (pos - offset, atom)).collect(),
+        bonds.iter().map(|&Bond { start, end, ty }| {
+            let (start, end) = (start - offset, end - offset);
+            Bond {
+                start: if (start.q, start.r) <= (end.q, end.r) { start } else { end },
+                end: if (start.q, start.r) <= (end.q, end.r) { end } else { start },
+                ty,
+            }
+        }).collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        JsState,
+        molecule_from_notation,
+    };
+
+    fn query(notation: &str) -> super::Query {
+        JsState::from_notation(notation).and_then(JsState::into_query).expect("invalid test query")
+    }
+
+    #[test]
+    fn part_in_any_position_and_rotation() {
+        // a chain of three salts with a fire bonded to the middle one
+        let molecule = molecule_from_notation("0,0=salt;1,0=salt;2,0=salt;1,1=fire;0,0:1,0=n;1,0:2,0=n;1,0:1,1=n").expect("invalid test molecule");
+        assert!(query("0,0=salt;1,0=salt;0,0:1,0=n").is_part_of(&molecule));
+        // the salt-fire bond is vertical in the molecule but horizontal in the query
+        assert!(query("5,5=fire;6,5=salt;5,5:6,5=n").is_part_of(&molecule));
+        assert!(query("0,0=salt;1,0=cardinal;0,0:1,0=n").is_part_of(&molecule));
+        assert!(!query("0,0=salt;1,0=metal;0,0:1,0=n").is_part_of(&molecule));
+    }
+
+    #[test]
+    fn part_needs_bonds() {
+        let molecule = molecule_from_notation("0,0=salt;1,0=salt;2,0=salt;0,0:1,0=n").expect("invalid test molecule");
+        assert!(query("0,0=salt;1,0=salt").is_part_of(&molecule));
+        assert!(!query("0,0=salt;1,0=salt;0,0:1,0=ryk").is_part_of(&molecule));
+        // the only two bonded salts are the first two, so there is no chain of three
+        assert!(!query("0,0=salt;1,0=salt;2,0=salt;0,0:1,0=n;1,0:2,0=n").is_part_of(&molecule));
+    }
+
+    #[test]
+    fn whole_molecule_is_part() {
+        let molecule = molecule_from_notation("0,0=water;0,1=air;0,0:0,1=n").expect("invalid test molecule");
+        assert!(query("0,0=water;0,1=air;0,0:0,1=n").matches(&molecule));
+        assert!(query("0,0=water;0,1=air;0,0:0,1=n").is_part_of(&molecule));
+    }
+}
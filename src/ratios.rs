@@ -0,0 +1,184 @@
+use {
+    std::collections::HashSet,
+    itertools::Itertools as _,
+    omsim_rs::data::*,
+    schemars::JsonSchema,
+    serde::Serialize,
+    crate::{
+        glyphs::{
+            ATOMS,
+            CARDINALS,
+            Glyph,
+            METALS,
+        },
+        puzzles::Puzzle,
+    },
+};
+
+/// The highest number of each product considered when looking for a balanced ratio.
+const MAX_PRODUCTS: usize = 6;
+/// The highest total number of reagents considered when looking for a balanced ratio.
+const MAX_REAGENTS: usize = 12;
+/// The maximum number of glyph applications tried when checking whether a given ratio can be balanced.
+const MAX_STEPS: usize = 48;
+
+type Counts = [i32; ATOMS.len()];
+
+fn idx(atom: Atom) -> usize {
+    ATOMS.iter().position(|&iter_atom| iter_atom == atom).expect("ATOMS contains every atom except repeat")
+}
+
+/// A single glyph application, as a change in atom counts.
+pub(crate) struct Reaction {
+    pub(crate) consumed: Vec<Atom>,
+    pub(crate) produced: Vec<Atom>,
+}
+
+pub(crate) fn reactions(glyph: Glyph) -> Vec<Reaction> {
+    match glyph {
+        Glyph::Calcification => CARDINALS.into_iter().map(|atom| Reaction { consumed: vec![atom], produced: vec![Atom::Salt] }).collect(),
+        // the cardinal is only copied, but it has to be there
+        Glyph::Duplication => CARDINALS.into_iter().map(|atom| Reaction { consumed: vec![Atom::Salt, atom], produced: vec![atom, atom] }).collect(),
+        Glyph::Projection => METALS.windows(2).map(|pair| Reaction { consumed: vec![Atom::Quicksilver, pair[0]], produced: vec![pair[1]] }).collect(),
+        Glyph::Purification => METALS.windows(2).map(|pair| Reaction { consumed: vec![pair[0], pair[0]], produced: vec![pair[1]] }).collect(),
+        Glyph::Animismus => vec![Reaction { consumed: vec![Atom::Salt, Atom::Salt], produced: vec![Atom::Vitae, Atom::Mors] }],
+        Glyph::Disposal => ATOMS.into_iter().map(|atom| Reaction { consumed: vec![atom], produced: Vec::default() }).collect(),
+        Glyph::Dispersion => vec![Reaction { consumed: vec![Atom::Quintessence], produced: CARDINALS.to_vec() }],
+        Glyph::Unification => vec![Reaction { consumed: CARDINALS.to_vec(), produced: vec![Atom::Quintessence] }],
+        Glyph::TriplexBonding => Vec::default(),
+    }
+}
+
+fn counts(molecule: &Molecule) -> Counts {
+    let mut counts = [0; ATOMS.len()];
+    for &atom in molecule.atoms.values() {
+        counts[idx(atom)] += 1;
+    }
+    counts
+}
+
+/// Checks whether the given surplus (positive) and deficit (negative) atom counts can be brought to zero using the given reactions.
+fn balances(surplus: Counts, reactions: &[Reaction], visited: &mut HashSet<Counts>, steps: usize) -> bool {
+    let Some(deficit) = surplus.iter().position(|&count| count < 0) else {
+        // any surplus left over has to be disposed of
+        return surplus.iter().zip(ATOMS).all(|(&count, atom)| count == 0 || reactions.iter().any(|reaction| reaction.consumed == [atom] && reaction.produced.is_empty()))
+    };
+    if steps == 0 || !visited.insert(surplus) {
+        return false
+    }
+    reactions.iter().filter(|reaction| reaction.produced.iter().any(|&atom| idx(atom) == deficit)).any(|reaction| {
+        let mut surplus = surplus;
+        for &atom in &reaction.consumed {
+            surplus[idx(atom)] -= 1;
+        }
+        for &atom in &reaction.produced {
+            surplus[idx(atom)] += 1;
+        }
+        balances(surplus, reactions, visited, steps - 1)
+    })
+}
+
+/// All ways to distribute `total` among `parts` non-negative numbers.
+fn compositions(total: usize, parts: usize) -> Vec<Vec<usize>> {
+    if parts == 1 {
+        return vec![vec![total]]
+    }
+    (0..=total).flat_map(|first| compositions(total - first, parts - 1).into_iter().map(move |mut rest| {
+        rest.insert(0, first);
+        rest
+    })).collect()
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Ratio {
+    /// How many of each product are made from the reagents.
+    pub(crate) products: usize,
+    /// How many of each reagent are needed, in the order the reagents are listed for the puzzle.
+    pub(crate) reagents: Vec<(&'static str, usize)>,
+}
+
+/// Whether [`minimal_ratio`] can find a ratio for the given puzzle at all, which isn't the case for puzzles with polymer products or without reagents.
+pub(crate) fn applies(puzzle: &Puzzle) -> bool {
+    !puzzle.reagents.is_empty() && !puzzle.reagents.iter().chain(&puzzle.products).any(|(molecule, _)| molecule.atoms.values().any(|&atom| atom == Atom::Repeat))
+}
+
+/// Finds the smallest numbers of reagents and products for which every reagent atom ends up in a product (or is disposed of), transforming atoms only with the given glyphs.
+///
+/// Returns `None` if the ratio doesn't [apply](applies) to the puzzle or if no such ratio exists within the search limits.
+pub(crate) fn minimal_ratio(puzzle: &Puzzle, glyphs: &[Glyph]) -> Option<Ratio> {
+    if !applies(puzzle) {
+        return None
+    }
+    let reactions = glyphs.iter().flat_map(|&glyph| reactions(glyph)).collect_vec();
+    let reagent_counts = puzzle.reagents.iter().map(|(molecule, _)| counts(molecule)).collect_vec();
+    let mut product_counts = [0; ATOMS.len()];
+    for (molecule, _) in &puzzle.products {
+        for (total, count) in product_counts.iter_mut().zip(counts(molecule)) {
+            *total += count;
+        }
+    }
+    for products in 1..=MAX_PRODUCTS {
+        for total in 1..=MAX_REAGENTS {
+            for split in compositions(total, reagent_counts.len()) {
+                let mut surplus = product_counts.map(|count| -count * products as i32);
+                for (count, reagent) in split.iter().zip(&reagent_counts) {
+                    for (surplus_count, atoms) in surplus.iter_mut().zip(reagent) {
+                        *surplus_count += atoms * *count as i32;
+                    }
+                }
+                if balances(surplus, &reactions, &mut HashSet::default(), MAX_STEPS) {
+                    return Some(Ratio {
+                        products,
+                        reagents: puzzle.reagents.iter().map(|(_, name)| *name).zip(split).collect(),
+                    })
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::puzzles,
+        super::*,
+    };
+
+    fn ratio(name: &str, glyphs: &[Glyph]) -> Option<(usize, Vec<(&'static str, usize)>)> {
+        let puzzle = puzzles::puzzle(name).expect("puzzle not in catalogue");
+        minimal_ratio(&puzzle, glyphs).map(|Ratio { products, reagents }| (products, reagents))
+    }
+
+    #[test]
+    fn calcification() {
+        assert_eq!(ratio("Lesson: Introduction", &[Glyph::Calcification]), Some((1, vec![("Elemental Fire", 3)])));
+        assert_eq!(ratio("Lesson: Introduction", &[]), None);
+        assert_eq!(ratio("Hangover Cure", &[Glyph::Calcification]), Some((1, vec![("Elemental Water", 3)])));
+    }
+
+    #[test]
+    fn projection() {
+        let (products, reagents) = ratio("Refined Gold", &[Glyph::Projection]).expect("no ratio found");
+        assert_eq!(products, 1);
+        assert!(reagents.contains(&("Elemental Lead", 1)));
+        assert!(reagents.contains(&("Elemental Quicksilver", 5)));
+    }
+
+    #[test]
+    fn duplication() {
+        // each reagent has one earth and one salt, but the product has 7 earth
+        assert_eq!(ratio("Viscous Sludge", &[Glyph::Duplication]), Some((2, vec![("Stabilized Earth", 7)])));
+        assert_eq!(ratio("Viscous Sludge", &[]), None);
+        // two of the salts become vitae and mors, the third one becomes air
+        assert_eq!(ratio("Vapor of Levity", &[Glyph::Animismus, Glyph::Duplication]), Some((1, vec![("Stabilized Air", 3)])));
+        assert_eq!(ratio("Vapor of Levity", &[Glyph::Animismus]), None);
+    }
+
+    #[test]
+    fn disposal() {
+        // the leftover salt is thrown away
+        assert_eq!(ratio("Vapor of Levity", &[Glyph::Animismus, Glyph::Disposal]), Some((1, vec![("Stabilized Air", 4)])));
+    }
+}
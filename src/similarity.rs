@@ -0,0 +1,62 @@
+use {
+    std::collections::HashMap,
+    omsim_rs::data::*,
+    crate::query::rotate_ccw,
+};
+
+/// The maximum edit distance for which known molecules are suggested as alternatives to an unknown one.
+pub(crate) const MAX_SUGGESTION_DISTANCE: usize = 3;
+
+fn bond_key(start: HexIndex, end: HexIndex) -> (HexIndex, HexIndex) {
+    if (start.q, start.r) <= (end.q, end.r) { (start, end) } else { (end, start) }
+}
+
+/// The number of atom substitutions, added or removed atoms, and added, removed, or changed bonds needed to turn `from` into `to`,
+/// minimized over all rotations and translations of `from`.
+///
+/// Returns `None` if the distance is greater than `max`.
+pub(crate) fn edit_distance(from: &Molecule, to: &Molecule, max: usize) -> Option<usize> {
+    // every atom or bond which only one of the molecules has needs at least one edit
+    let lower_bound = from.atoms.len().abs_diff(to.atoms.len()) + from.bonds.len().abs_diff(to.bonds.len());
+    if lower_bound > max {
+        return None
+    }
+    let to_bonds = to.bonds.iter().map(|bond| (bond_key(bond.start, bond.end), bond.ty)).collect::<HashMap<_, _>>();
+    let rotations = if from.atoms.values().chain(to.atoms.values()).any(|&atom| atom == Atom::Repeat) { 1 } else { 6 };
+    let mut atoms = from.atoms.clone();
+    let mut bonds = from.bonds.iter().map(|bond| ((bond.start, bond.end), bond.ty)).collect::<Vec<_>>();
+    let mut best = None::<usize>;
+    for _ in 0..rotations {
+        for &from_pos in atoms.keys() {
+            for &to_pos in to.atoms.keys() {
+                let offset = to_pos - from_pos;
+                let mut distance = 0;
+                for (&pos, &atom) in &atoms {
+                    if to.atoms.get(&(pos + offset)) != Some(&atom) {
+                        distance += 1;
+                    }
+                }
+                distance += to.atoms.keys().filter(|&&pos| !atoms.contains_key(&(pos - offset))).count();
+                let mut matched_bonds = 0;
+                for &((start, end), ty) in &bonds {
+                    match to_bonds.get(&bond_key(start + offset, end + offset)) {
+                        Some(&to_ty) => {
+                            matched_bonds += 1;
+                            if to_ty != ty {
+                                distance += 1;
+                            }
+                        }
+                        None => distance += 1,
+                    }
+                }
+                distance += to_bonds.len() - matched_bonds;
+                if distance <= best.unwrap_or(max) {
+                    best = Some(distance);
+                }
+            }
+        }
+        atoms = atoms.into_iter().map(|(pos, atom)| (rotate_ccw(pos), atom)).collect();
+        bonds = bonds.into_iter().map(|((start, end), ty)| ((rotate_ccw(start), rotate_ccw(end)), ty)).collect();
+    }
+    best
+}
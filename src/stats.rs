@@ -0,0 +1,101 @@
+use {
+    std::cmp::Reverse,
+    enum_iterator::all,
+    itertools::Itertools as _,
+    omsim_rs::data::*,
+    schemars::JsonSchema,
+    serde::Serialize,
+    crate::{
+        MoleculeExt as _,
+        display_name,
+        format_atom,
+        glyphs::ATOMS,
+        molecules,
+        puzzles::Source,
+    },
+};
+
+/// The number of molecules listed as most reused and largest.
+const TOP_COUNT: usize = 10;
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MoleculeCount {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) count: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Stats {
+    pub(crate) molecules: usize,
+    pub(crate) appearances_by_source: Vec<(Source, usize)>,
+    /// For each atom type, the number of atoms of that type across all molecules.
+    pub(crate) atom_frequency: Vec<(&'static str, usize)>,
+    /// For each number of atoms, the number of molecules of that size.
+    pub(crate) size_distribution: Vec<(usize, usize)>,
+    /// Molecules with the most appearances.
+    pub(crate) most_reused: Vec<MoleculeCount>,
+    /// Molecules with the most atoms.
+    pub(crate) largest: Vec<MoleculeCount>,
+    /// Molecules with at least one triplex bond.
+    pub(crate) triplex: usize,
+    pub(crate) polymers: usize,
+    /// For each symmetry class, the number of molecules in it. Polymers aren't included.
+    pub(crate) symmetry: Vec<(&'static str, usize)>,
+}
+
+/// The symmetry group of a molecule as a Schoenflies-like name: `C` for rotational symmetry only or `D` if the molecule is also symmetric under reflection, followed by the number of rotations which map the molecule to itself.
+fn symmetry_class(molecule: &Molecule) -> &'static str {
+    let normalized = molecule.position_normalized();
+    let rotations = all().filter(|&rotation| molecule.rotated(HexIndex::default(), rotation).position_normalized() == normalized).count();
+    let mirrored = molecule.mirrored();
+    let reflective = all().any(|rotation| mirrored.rotated(HexIndex::default(), rotation).position_normalized() == normalized);
+    match (reflective, rotations) {
+        (false, 1) => "C1",
+        (false, 2) => "C2",
+        (false, 3) => "C3",
+        (false, _) => "C6",
+        (true, 1) => "D1",
+        (true, 2) => "D2",
+        (true, 3) => "D3",
+        (true, _) => "D6",
+    }
+}
+
+impl Stats {
+    pub(crate) fn compute() -> Self {
+        let molecules = molecules::molecules();
+        let is_polymer = |molecule: &Molecule| molecule.atoms.values().any(|&atom| atom == Atom::Repeat);
+        Self {
+            molecules: molecules.len(),
+            appearances_by_source: all::<Source>()
+                .map(|source| (source, molecules.iter().flat_map(|(_, appearances)| appearances).filter(|(puzzle, _, _)| Source::of(puzzle) == Some(source)).count()))
+                .collect(),
+            atom_frequency: ATOMS.into_iter()
+                .map(|atom| (format_atom(atom), molecules.iter().map(|(molecule, _)| molecule.atoms.values().filter(|&&iter_atom| iter_atom == atom).count()).sum()))
+                .collect(),
+            size_distribution: molecules.iter().map(|(molecule, _)| molecule.atoms.len()).counts().into_iter().sorted_unstable().collect(),
+            most_reused: molecules.iter()
+                .sorted_by_key(|(_, appearances)| Reverse(appearances.len()))
+                .take(TOP_COUNT)
+                .map(|(molecule, appearances)| MoleculeCount { id: molecule.id(), name: display_name(appearances), count: appearances.len() })
+                .collect(),
+            largest: molecules.iter()
+                .sorted_by_key(|(molecule, _)| Reverse(molecule.atoms.len()))
+                .take(TOP_COUNT)
+                .map(|(molecule, appearances)| MoleculeCount { id: molecule.id(), name: display_name(appearances), count: molecule.atoms.len() })
+                .collect(),
+            triplex: molecules.iter().filter(|(molecule, _)| molecule.bonds.iter().any(|bond| matches!(bond.ty, BondType::Triplex { .. }))).count(),
+            polymers: molecules.iter().filter(|(molecule, _)| is_polymer(molecule)).count(),
+            symmetry: molecules.iter()
+                .filter(|(molecule, _)| !is_polymer(molecule))
+                .map(|(molecule, _)| symmetry_class(molecule))
+                .counts()
+                .into_iter()
+                .sorted_unstable()
+                .collect(),
+        }
+    }
+}
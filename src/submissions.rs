@@ -0,0 +1,115 @@
+use {
+    std::{
+        path::Path,
+        sync::Mutex,
+    },
+    chrono::prelude::*,
+    rusqlite::Connection,
+    crate::{
+        InOut,
+        export,
+    },
+};
+
+/// A queue of molecule names submitted by visitors, waiting for review by an admin.
+pub(crate) struct Submissions(Option<Mutex<Connection>>);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Review {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl Review {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+pub(crate) struct Submission {
+    pub(crate) id: i64,
+    /// The molecule in the builder's text notation, see [`crate::MoleculeExt::notation`].
+    pub(crate) notation: String,
+    pub(crate) puzzle: String,
+    pub(crate) role: InOut,
+    pub(crate) name: String,
+    pub(crate) source: String,
+    pub(crate) submitted: DateTime<Utc>,
+}
+
+fn parse_role(role: &str) -> Option<InOut> {
+    match role {
+        "reagent" => Some(InOut::Reagent),
+        "product" => Some(InOut::Product),
+        "both" => Some(InOut::Both),
+        _ => None,
+    }
+}
+
+impl Submissions {
+    /// Opens or creates the queue database at the given path, or returns a disabled queue if there is none.
+    pub(crate) fn open(path: Option<&Path>) -> rusqlite::Result<Self> {
+        let Some(path) = path else { return Ok(Self(None)) };
+        let db = Connection::open(path)?;
+        db.execute_batch("CREATE TABLE IF NOT EXISTS submissions (
+            id INTEGER PRIMARY KEY,
+            notation TEXT NOT NULL,
+            puzzle TEXT NOT NULL,
+            role TEXT NOT NULL,
+            name TEXT NOT NULL,
+            source TEXT NOT NULL,
+            submitted TEXT NOT NULL,
+            review TEXT NOT NULL DEFAULT 'pending'
+        )")?;
+        Ok(Self(Some(Mutex::new(db))))
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Checks that the queue database can be queried. Does nothing if the queue is disabled.
+    pub(crate) fn check(&self) -> rusqlite::Result<()> {
+        let Some(ref db) = self.0 else { return Ok(()) };
+        db.lock().expect("submission queue poisoned").query_row("SELECT 1", (), |_| Ok(()))
+    }
+
+    /// Adds a submission to the queue. Returns `false` if the queue is disabled.
+    pub(crate) fn submit(&self, notation: &str, puzzle: &str, role: &InOut, name: &str, source: &str) -> rusqlite::Result<bool> {
+        let Some(ref db) = self.0 else { return Ok(false) };
+        db.lock().expect("submission queue poisoned").execute(
+            "INSERT INTO submissions (notation, puzzle, role, name, source, submitted) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            (notation, puzzle, export::role(role), name, source, Utc::now()),
+        )?;
+        Ok(true)
+    }
+
+    /// All submissions with the given review status, oldest first, or `None` if the queue is disabled.
+    pub(crate) fn with_review(&self, review: Review) -> rusqlite::Result<Option<Vec<Submission>>> {
+        let Some(ref db) = self.0 else { return Ok(None) };
+        let db = db.lock().expect("submission queue poisoned");
+        let mut query = db.prepare("SELECT id, notation, puzzle, role, name, source, submitted FROM submissions WHERE review = ?1 ORDER BY submitted")?;
+        let submissions = query.query_map((review.as_str(),), |row| Ok(Submission {
+            id: row.get(0)?,
+            notation: row.get(1)?,
+            puzzle: row.get(2)?,
+            role: parse_role(&row.get::<_, String>(3)?).ok_or_else(|| rusqlite::Error::InvalidColumnType(3, "role".to_owned(), rusqlite::types::Type::Text))?,
+            name: row.get(4)?,
+            source: row.get(5)?,
+            submitted: row.get(6)?,
+        }))?.collect::<rusqlite::Result<_>>()?;
+        Ok(Some(submissions))
+    }
+
+    /// Sets the review status of a submission. Returns `false` if there is no such submission or the queue is disabled.
+    pub(crate) fn review(&self, id: i64, review: Review) -> rusqlite::Result<bool> {
+        let Some(ref db) = self.0 else { return Ok(false) };
+        let updated = db.lock().expect("submission queue poisoned").execute("UPDATE submissions SET review = ?1 WHERE id = ?2", (review.as_str(), id))?;
+        Ok(updated > 0)
+    }
+}
@@ -0,0 +1,119 @@
+use {
+    std::{
+        collections::HashMap,
+        path::Path,
+        sync::{
+            Arc,
+            Mutex,
+            mpsc,
+        },
+        thread,
+    },
+    chrono::prelude::*,
+    omsim_rs::data::*,
+    rusqlite::Connection,
+    crate::{
+        MoleculeExt as _,
+        unparse::Unparse,
+    },
+};
+
+/// An opt-in record of lookups which didn't match any known molecule, as hints for missing catalogue entries.
+pub(crate) struct UnknownLog(Option<Log>);
+
+struct Log {
+    db: Arc<Mutex<Connection>>,
+    /// Lookups are recorded by a background thread so request handlers don't block on database writes.
+    writer: mpsc::Sender<Batch>,
+}
+
+/// The unknown molecules looked up by one request, so each distinct molecule is only counted once per request.
+#[derive(Default)]
+pub(crate) struct Batch(HashMap<String, Molecule>);
+
+impl Batch {
+    /// Adds the given normalized molecule.
+    pub(crate) fn add(&mut self, molecule: &Molecule) {
+        self.0.entry(molecule.id()).or_insert_with(|| molecule.clone());
+    }
+}
+
+fn write(db: &Mutex<Connection>, Batch(molecules): Batch) -> rusqlite::Result<()> {
+    let now = Utc::now();
+    let mut db = db.lock().expect("unknown molecule log poisoned");
+    let transaction = db.transaction()?;
+    for (id, molecule) in molecules {
+        transaction.execute(
+            "INSERT INTO unknown_molecules (id, notation, rust_code, hits, first_seen, last_seen) VALUES (?1, ?2, ?3, 1, ?4, ?4)
+            ON CONFLICT (id) DO UPDATE SET hits = hits + 1, last_seen = excluded.last_seen",
+            (id, molecule.notation(), format!("{:?}", Unparse(&molecule)), now),
+        )?;
+    }
+    transaction.commit()
+}
+
+pub(crate) struct Entry {
+    /// The molecule in the builder's text notation, see [`crate::MoleculeExt::notation`].
+    pub(crate) notation: String,
+    pub(crate) rust_code: String,
+    pub(crate) hits: i64,
+    pub(crate) first_seen: DateTime<Utc>,
+    pub(crate) last_seen: DateTime<Utc>,
+}
+
+impl UnknownLog {
+    /// Opens or creates the log database at the given path, or returns a disabled log if there is none.
+    pub(crate) fn open(path: Option<&Path>) -> rusqlite::Result<Self> {
+        let Some(path) = path else { return Ok(Self(None)) };
+        let db = Connection::open(path)?;
+        db.execute_batch("CREATE TABLE IF NOT EXISTS unknown_molecules (
+            id TEXT PRIMARY KEY NOT NULL,
+            notation TEXT NOT NULL,
+            rust_code TEXT NOT NULL,
+            hits INTEGER NOT NULL,
+            first_seen TEXT NOT NULL,
+            last_seen TEXT NOT NULL
+        )")?;
+        let db = Arc::new(Mutex::new(db));
+        let (writer, batches) = mpsc::channel();
+        let writer_db = Arc::clone(&db);
+        thread::Builder::new().name("unknown-molecule-log".to_owned()).spawn(move || for batch in batches {
+            if let Err(e) = write(&writer_db, batch) {
+                rocket::error!("failed to record unknown molecules: {e}");
+            }
+        }).expect("failed to start unknown molecule log writer");
+        Ok(Self(Some(Log { db, writer })))
+    }
+
+    /// Checks that the log database can be queried. Does nothing if the log is disabled.
+    pub(crate) fn check(&self) -> rusqlite::Result<()> {
+        let Some(ref log) = self.0 else { return Ok(()) };
+        log.db.lock().expect("unknown molecule log poisoned").query_row("SELECT 1", (), |_| Ok(()))
+    }
+
+    /// Counts a lookup of each molecule in the batch in the background. Does nothing if the log is disabled.
+    pub(crate) fn record(&self, batch: Batch) {
+        let Some(ref log) = self.0 else { return };
+        if batch.0.is_empty() {
+            return
+        }
+        if log.writer.send(batch).is_err() {
+            rocket::error!("failed to record unknown molecules: the log writer has stopped");
+        }
+    }
+
+    /// The most frequently looked up unknown molecules, or `None` if the log is disabled.
+    pub(crate) fn most_frequent(&self, limit: usize) -> rusqlite::Result<Option<Vec<Entry>>> {
+        let Some(ref log) = self.0 else { return Ok(None) };
+        let db = log.db.lock().expect("unknown molecule log poisoned");
+        let mut query = db.prepare("SELECT notation, rust_code, hits, first_seen, last_seen FROM unknown_molecules ORDER BY hits DESC, last_seen DESC LIMIT ?1")?;
+        let entries = query.query_map((limit,), |row| Ok(Entry {
+            notation: row.get(0)?,
+            rust_code: row.get(1)?,
+            hits: row.get(2)?,
+            first_seen: row.get(3)?,
+            last_seen: row.get(4)?,
+        }))?.collect::<rusqlite::Result<_>>()?;
+        Ok(Some(entries))
+    }
+}
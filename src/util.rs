@@ -61,3 +61,13 @@ pub(crate) trait IteratorExt: Iterator {
 }
 
 impl<T: Iterator> IteratorExt for T {}
+
+/// 64-bit FNV-1a, used for identifiers which must not change between builds.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Compares two byte strings in a time which only depends on their lengths, so a secret can't be guessed byte by byte from response times.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && std::hint::black_box(a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b))) == 0
+}